@@ -0,0 +1,110 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::models::{Repository, RepositoryCollection, RepositoryError};
+use crate::models::api_key::ApiKey;
+use crate::settings::Auth;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+#[derive(Debug)]
+pub enum AuthError {
+    MissingKey,
+    InvalidKey,
+    /// Valid key without access to the `/admin` endpoints
+    Forbidden,
+    QuotaExceeded { retry_after_secs: u64 },
+    Repository(RepositoryError),
+}
+
+impl From<RepositoryError> for AuthError {
+    fn from(e: RepositoryError) -> Self {
+        AuthError::Repository(e)
+    }
+}
+
+/// Validates API keys against `Settings` and the `api_keys` collection and enforces their daily quotas.
+/// Usage is counted per UTC day in the `api_key_usage` collection, so that it survives restarts and is shared by every instance.
+#[derive(Clone)]
+pub struct ApiKeyAuthenticator {
+    config: Auth,
+    repos: RepositoryCollection,
+}
+
+impl ApiKeyAuthenticator {
+    pub fn new(config: Auth, repos: RepositoryCollection) -> Self {
+        ApiKeyAuthenticator {
+            config,
+            repos,
+        }
+    }
+
+    pub fn header(&self) -> &str {
+        self.config.header.as_str()
+    }
+
+    /// Returns `Ok(None)` when authentication is disabled.
+    pub fn authenticate(&self, key: Option<&str>) -> Result<Option<ApiKey>, AuthError> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+
+        let key = key.ok_or(AuthError::MissingKey)?;
+        let api_key = self.find_key(key)?.ok_or(AuthError::InvalidKey)?;
+
+        if api_key.disabled {
+            return Err(AuthError::InvalidKey);
+        }
+
+        self.consume_quota(&api_key)?;
+
+        Ok(Some(api_key))
+    }
+
+    /// Admin keys are required even when authentication is disabled, and have no quota.
+    pub fn authenticate_admin(&self, key: Option<&str>) -> Result<ApiKey, AuthError> {
+        let key = key.ok_or(AuthError::MissingKey)?;
+        let api_key = self.find_key(key)?.ok_or(AuthError::InvalidKey)?;
+
+        if api_key.disabled {
+            return Err(AuthError::InvalidKey);
+        }
+
+        if !api_key.admin {
+            return Err(AuthError::Forbidden);
+        }
+
+        Ok(api_key)
+    }
+
+    fn find_key(&self, key: &str) -> Result<Option<ApiKey>, AuthError> {
+        if let Some(config) = self.config.keys.iter().find(|k| k.key == key) {
+            let mut api_key = ApiKey::new();
+            api_key.key = config.key.clone();
+            api_key.name = config.name.clone();
+            api_key.daily_quota = config.daily_quota;
+            api_key.admin = config.admin;
+            return Ok(Some(api_key));
+        }
+
+        Ok(self.repos.api_key.get(doc! {"key": key})?)
+    }
+
+    /// Keys without a quota are not counted. Refused requests are counted too, which only matters past the quota.
+    fn consume_quota(&self, api_key: &ApiKey) -> Result<(), AuthError> {
+        if api_key.daily_quota <= 0 {
+            return Ok(());
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let day = now / SECONDS_PER_DAY;
+
+        let count = self.repos.api_key_usage.increment(&api_key.key, day as i64)?;
+
+        if count > api_key.daily_quota {
+            return Err(AuthError::QuotaExceeded {
+                retry_after_secs: SECONDS_PER_DAY - now % SECONDS_PER_DAY,
+            });
+        }
+
+        Ok(())
+    }
+}
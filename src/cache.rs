@@ -0,0 +1,95 @@
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use lru::LruCache;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// Bounded LRU cache whose entries also expire after a fixed time to live.
+/// A cache created with a capacity of 0 never stores anything.
+#[derive(Clone)]
+pub struct TtlCache<K: Hash + Eq, V: Clone> {
+    entries: Option<Arc<Mutex<LruCache<K, (Instant, V)>>>>,
+    capacity: usize,
+    ttl: Duration,
+    hits: Arc<AtomicUsize>,
+    misses: Arc<AtomicUsize>,
+}
+
+impl<K: Hash + Eq, V: Clone> TtlCache<K, V> {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let entries = if capacity > 0 {
+            Some(Arc::new(Mutex::new(LruCache::new(capacity))))
+        } else {
+            None
+        };
+
+        TtlCache {
+            entries,
+            capacity,
+            ttl,
+            hits: Arc::new(AtomicUsize::new(0)),
+            misses: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let entries = match self.entries {
+            Some(ref entries) => entries,
+            None => return None,
+        };
+
+        let mut entries = entries.lock().expect("Cache lock poisoned");
+
+        let (value, expired) = match entries.get(key) {
+            Some((inserted, value)) if inserted.elapsed() < self.ttl => (Some(value.clone()), false),
+            Some(_) => (None, true),
+            None => (None, false),
+        };
+
+        if expired {
+            entries.pop(key);
+        }
+
+        if value.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        value
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if let Some(ref entries) = self.entries {
+            entries.lock().expect("Cache lock poisoned").put(key, (Instant::now(), value));
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Some(ref entries) = self.entries {
+            entries.lock().expect("Cache lock poisoned").clear();
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = match self.entries {
+            Some(ref entries) => entries.lock().expect("Cache lock poisoned").len(),
+            None => 0,
+        };
+
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries,
+            capacity: self.capacity,
+        }
+    }
+}
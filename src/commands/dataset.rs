@@ -0,0 +1,37 @@
+use crate::models::RepositoryCollection;
+use crate::settings::DatasetCommand;
+
+pub fn run(repos: &RepositoryCollection, command: &DatasetCommand) -> Result<(), String> {
+    match command {
+        DatasetCommand::List => {
+            let active = repos.dataset_version();
+
+            for version in repos.list_datasets().map_err(|e| format!("{:?}", e))? {
+                let marker = if active.as_ref() == Some(&version) { "*" } else { " " };
+                println!("{} {}", marker, version);
+            }
+
+            if active.is_none() {
+                println!("* (unversioned ip and location collections)");
+            }
+
+            Ok(())
+        }
+        DatasetCommand::Activate(version) => {
+            repos.activate_dataset(version).map_err(|e| format!("{:?}", e))?;
+            println!("Dataset {} is now active", version);
+            Ok(())
+        }
+        DatasetCommand::Rollback => {
+            let version = repos.rollback_dataset().map_err(|e| format!("{:?}", e))?;
+
+            if version.is_empty() {
+                println!("Rolled back to the unversioned ip and location collections");
+            } else {
+                println!("Rolled back to dataset {}", version);
+            }
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::thread;
+use serde_json::{Map, Value};
+use crate::lookup::{LocationSource, Locator, LookupError};
+use crate::settings::{EnrichCommand, EnrichFormat};
+
+const BATCH_SIZE: usize = 10_000;
+
+/// Appended to every row, prefixed so that they cannot clash with the input columns
+const GEO_COLUMNS: &[&str] = &[
+    "geo_network",
+    "geo_latitude",
+    "geo_longitude",
+    "geo_accuracy_radius_km",
+    "geo_continent",
+    "geo_country",
+    "geo_country_iso_code",
+    "geo_subdivision_1",
+    "geo_subdivision_2",
+    "geo_city",
+    "geo_time_zone",
+    "geo_location_source",
+    "geo_error",
+];
+
+pub fn run(locator: &Locator, command: &EnrichCommand) -> Result<(), String> {
+    let input: Box<dyn Read> = if command.input == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(&command.input).map_err(|e| format!("Unable to open {}: {}", command.input, e))?)
+    };
+
+    let output: Box<dyn Write> = if command.output == "-" {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(&command.output).map_err(|e| format!("Unable to create {}: {}", command.output, e))?)
+    };
+
+    let mut rows = match command.column {
+        Some(ref column) => Rows::csv(input, column)?,
+        None => Rows::lines(input),
+    };
+
+    let mut writer = RowWriter::new(BufWriter::new(output), command.format, rows.headers())?;
+    let threads = command.threads.max(1);
+    let mut count = 0;
+
+    loop {
+        let batch = rows.next_batch(BATCH_SIZE)?;
+        if batch.is_empty() {
+            break;
+        }
+
+        count += batch.len();
+        let ips = batch.iter().map(|(_, ip)| ip.clone()).collect::<Vec<String>>();
+
+        for ((row, _), geo) in batch.into_iter().zip(lookup_batch(locator, ips, threads)?) {
+            writer.write(&row, &geo)?;
+        }
+    }
+
+    writer.flush()?;
+
+    // stdout carries the output itself
+    if command.output != "-" {
+        println!("Enriched {} rows into {}", count, command.output);
+    }
+
+    Ok(())
+}
+
+/// Looks `ips` up on `threads` threads, returning the geo columns in the order of `ips`.
+fn lookup_batch(locator: &Locator, ips: Vec<String>, threads: usize) -> Result<Vec<Vec<String>>, String> {
+    let chunk_size = (ips.len() + threads - 1) / threads;
+
+    let handles = ips.chunks(chunk_size.max(1))
+        .map(|chunk| {
+            let locator = locator.clone();
+            let chunk = chunk.to_vec();
+
+            thread::spawn(move || chunk.iter().map(|ip| geo_columns(&locator, ip)).collect::<Result<Vec<Vec<String>>, String>>())
+        })
+        .collect::<Vec<_>>();
+
+    let mut columns = Vec::with_capacity(ips.len());
+    for handle in handles {
+        columns.extend(handle.join().map_err(|_| "A lookup thread panicked".to_string())??);
+    }
+
+    Ok(columns)
+}
+
+/// The geo columns of one IP. Only database errors abort the run, other failures are reported in `geo_error`.
+fn geo_columns(locator: &Locator, ip: &str) -> Result<Vec<String>, String> {
+    let addr = match IpAddr::from_str(ip.trim()) {
+        Ok(addr) => addr,
+        Err(_) => return Ok(error_columns("invalid_ip")),
+    };
+
+    let result = match locator.lookup(addr) {
+        Ok(result) => result,
+        Err(LookupError::Repository(e)) => return Err(format!("Lookup of {} failed: {:?}", addr, e)),
+        Err(LookupError::Reserved(_)) => return Ok(error_columns("reserved_address")),
+        Err(LookupError::UnsupportedAddress) => return Ok(error_columns("unsupported_address")),
+        Err(LookupError::NetworkNotFound) | Err(LookupError::LocationNotFound) => return Ok(error_columns("not_found")),
+    };
+
+    let location_source = match result.location_source {
+        LocationSource::Override => "override",
+        LocationSource::Geoname => "geoname",
+        LocationSource::RegisteredCountry => "registered_country",
+        LocationSource::RepresentedCountry => "represented_country",
+    };
+
+    Ok(vec![
+        result.ip.network,
+        result.ip.latitude,
+        result.ip.longitude,
+        result.ip.accuracy_radius,
+        result.location.continent_name,
+        result.location.country_name,
+        result.location.country_iso_code,
+        result.location.subdivision_1_name,
+        result.location.subdivision_2_name,
+        result.location.city_name,
+        result.location.time_zone,
+        location_source.to_string(),
+        String::new(),
+    ])
+}
+
+fn error_columns(error: &str) -> Vec<String> {
+    let mut columns = vec![String::new(); GEO_COLUMNS.len() - 1];
+    columns.push(error.to_string());
+    columns
+}
+
+/// Input rows with the IP to look up: the lines of a plain file, or the records of a CSV file with a header.
+enum Rows {
+    Lines(io::Lines<BufReader<Box<dyn Read>>>),
+    Csv {
+        reader: csv::Reader<Box<dyn Read>>,
+        headers: Vec<String>,
+        column: usize,
+    },
+}
+
+impl Rows {
+    fn lines(input: Box<dyn Read>) -> Self {
+        Rows::Lines(BufReader::new(input).lines())
+    }
+
+    fn csv(input: Box<dyn Read>, column: &str) -> Result<Self, String> {
+        let mut reader = csv::Reader::from_reader(input);
+        let headers = reader.headers().map_err(|e| format!("Unable to read the CSV header: {}", e))?
+            .iter()
+            .map(|header| header.to_string())
+            .collect::<Vec<String>>();
+
+        let index = headers.iter().position(|header| header == column).ok_or_else(|| format!("The input has no {:?} column", column))?;
+
+        Ok(Rows::Csv {
+            reader,
+            headers,
+            column: index,
+        })
+    }
+
+    fn headers(&self) -> Vec<String> {
+        match self {
+            Rows::Lines(_) => vec!["ip".to_string()],
+            Rows::Csv { headers, .. } => headers.clone(),
+        }
+    }
+
+    /// Up to `size` rows with their IP, empty once the input is exhausted. Blank lines are skipped.
+    fn next_batch(&mut self, size: usize) -> Result<Vec<(Vec<String>, String)>, String> {
+        let mut batch = Vec::with_capacity(size);
+
+        match self {
+            Rows::Lines(lines) => {
+                while batch.len() < size {
+                    let line = match lines.next() {
+                        Some(line) => line.map_err(|e| format!("Unable to read the input: {}", e))?,
+                        None => break,
+                    };
+
+                    let ip = line.trim().to_string();
+                    if !ip.is_empty() {
+                        batch.push((vec![ip.clone()], ip));
+                    }
+                }
+            }
+            Rows::Csv { reader, column, .. } => {
+                let mut records = reader.records();
+
+                while batch.len() < size {
+                    let record = match records.next() {
+                        Some(record) => record.map_err(|e| format!("Unable to read the input: {}", e))?,
+                        None => break,
+                    };
+
+                    let ip = record.get(*column).unwrap_or_default().to_string();
+                    batch.push((record.iter().map(|field| field.to_string()).collect(), ip));
+                }
+            }
+        }
+
+        Ok(batch)
+    }
+}
+
+/// Writes the input columns followed by the geo columns, as CSV or as one JSON object per line.
+enum RowWriter<W: Write> {
+    Csv(csv::Writer<W>),
+    JsonLines {
+        output: W,
+        headers: Vec<String>,
+    },
+}
+
+impl<W: Write> RowWriter<W> {
+    fn new(output: W, format: EnrichFormat, input_headers: Vec<String>) -> Result<Self, String> {
+        let mut headers = input_headers;
+        headers.extend(GEO_COLUMNS.iter().map(|column| column.to_string()));
+
+        match format {
+            EnrichFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(output);
+                writer.write_record(&headers).map_err(|e| format!("Unable to write the output: {}", e))?;
+                Ok(RowWriter::Csv(writer))
+            }
+            EnrichFormat::JsonLines => Ok(RowWriter::JsonLines {
+                output,
+                headers,
+            }),
+        }
+    }
+
+    fn write(&mut self, row: &[String], geo: &[String]) -> Result<(), String> {
+        match self {
+            RowWriter::Csv(writer) => writer.write_record(row.iter().chain(geo.iter())).map_err(|e| format!("Unable to write the output: {}", e)),
+            RowWriter::JsonLines { output, headers } => {
+                let object = headers.iter()
+                    .zip(row.iter().chain(geo.iter()))
+                    .map(|(header, value)| (header.clone(), Value::String(value.clone())))
+                    .collect::<Map<String, Value>>();
+
+                writeln!(output, "{}", Value::Object(object)).map_err(|e| format!("Unable to write the output: {}", e))
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        let result = match self {
+            RowWriter::Csv(writer) => writer.flush(),
+            RowWriter::JsonLines { output, .. } => output.flush(),
+        };
+
+        result.map_err(|e| format!("Unable to write the output: {}", e))
+    }
+}
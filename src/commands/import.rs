@@ -0,0 +1,175 @@
+use std::mem;
+use ipnet::Ipv4Net;
+use serde::de::DeserializeOwned;
+use crate::geo::valid_coordinates;
+use crate::models::{Repository, RepositoryCollection, RepositoryError, unix_timestamp};
+use crate::models::dataset::DatasetInfo;
+use crate::models::ip::{GeoPoint, Ip};
+use crate::models::location::Location;
+use crate::settings::ImportCommand;
+
+const BATCH_SIZE: usize = 10_000;
+
+/// A row of a GeoLite2 `*-Blocks-IPv4.csv` file
+#[derive(Deserialize)]
+struct BlockRecord {
+    network: String,
+    #[serde(default)]
+    geoname_id: String,
+    #[serde(default)]
+    registered_country_geoname_id: String,
+    #[serde(default)]
+    represented_country_geoname_id: String,
+    #[serde(default)]
+    latitude: String,
+    #[serde(default)]
+    longitude: String,
+    #[serde(default)]
+    accuracy_radius: String,
+}
+
+/// A row of a GeoLite2 `*-Locations-<locale>.csv` file
+#[derive(Deserialize)]
+struct LocationRecord {
+    geoname_id: String,
+    #[serde(default)]
+    continent_name: String,
+    #[serde(default)]
+    country_iso_code: String,
+    #[serde(default)]
+    country_name: String,
+    #[serde(default)]
+    subdivision_1_name: String,
+    #[serde(default)]
+    subdivision_2_name: String,
+    #[serde(default)]
+    city_name: String,
+    #[serde(default)]
+    time_zone: String,
+}
+
+pub fn run(repos: &RepositoryCollection, command: &ImportCommand) -> Result<(), String> {
+    let target = repos.pinned_to(&command.version).map_err(|e| format!("{:?}", e))?;
+
+    if target.ip.count(None).map_err(|e| format!("{:?}", e))? > 0 {
+        return Err(format!("Dataset {} already exists, import under another version", command.version));
+    }
+
+    println!("Importing locations from {}..", command.locations);
+    let imported = import_csv(&command.locations, location_from_record, |batch| target.location.insert_all(batch))
+        .and_then(|_| {
+            println!("Importing networks from {}..", command.blocks);
+            import_csv(&command.blocks, ip_from_record, |batch| target.ip.insert_all(batch))
+        });
+
+    // A partial dataset must neither be recorded nor activated, nor prevent importing the same version again
+    if let Err(e) = imported {
+        let _ = target.ip.get_collection().map(|collection| collection.drop());
+        let _ = target.location.get_collection().map(|collection| collection.drop());
+        return Err(format!("{}, dataset {} was discarded", e, command.version));
+    }
+
+    println!("Building indexes..");
+    target.ensure_indexes().map_err(|e| format!("{:?}", e))?;
+
+    let mut info = DatasetInfo::new(&command.version);
+    info.source = command.source.clone();
+    info.build_date = command.build_date.clone();
+    info.ip_count = target.ip.count(None).map_err(|e| format!("{:?}", e))?;
+    info.location_count = target.location.count(None).map_err(|e| format!("{:?}", e))?;
+    info.imported_at = unix_timestamp();
+
+    println!("Imported {} networks and {} locations as dataset {}", info.ip_count, info.location_count, command.version);
+    repos.dataset_info.upsert(doc! {"_id": info.id.clone()}, info).map_err(|e| format!("{:?}", e))?;
+
+    if command.activate {
+        repos.activate_dataset(&command.version).map_err(|e| format!("{:?}", e))?;
+        println!("Dataset {} is now active", command.version);
+    }
+
+    Ok(())
+}
+
+/// Inserts the valid records of `path`, failing unless every one of them was written.
+fn import_csv<R, M, C, I>(path: &str, convert: C, insert: I) -> Result<(), String>
+    where R: DeserializeOwned,
+          C: Fn(R) -> Result<M, String>,
+          I: Fn(Vec<M>) -> Result<i64, RepositoryError>,
+{
+    let mut reader = csv::Reader::from_path(path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut read = 0;
+    let mut inserted = 0;
+
+    for (index, record) in reader.deserialize::<R>().enumerate() {
+        let record = record.map_err(|e| format!("{}: {}", path, e))?;
+
+        match convert(record) {
+            Ok(model) => {
+                read += 1;
+                batch.push(model);
+            }
+            // index 0 is the second line of the file, after the header
+            Err(e) => warn!("Skipping line {} of {}: {}", index + 2, path, e),
+        }
+
+        if batch.len() >= BATCH_SIZE {
+            inserted += insert(mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE))).map_err(|e| format!("Unable to import {}: {:?}", path, e))?;
+        }
+    }
+
+    inserted += insert(batch).map_err(|e| format!("Unable to import {}: {:?}", path, e))?;
+
+    if inserted != read {
+        return Err(format!("Only {} of the {} records of {} were written", inserted, read, path));
+    }
+
+    Ok(())
+}
+
+fn ip_from_record(record: BlockRecord) -> Result<Ip, String> {
+    let net = record.network.parse::<Ipv4Net>().map_err(|_| format!("Malformed network {}", record.network))?;
+
+    if net != net.trunc() {
+        return Err(format!("Malformed network {}: host bits are set", record.network));
+    }
+
+    let mut ip = Ip::new();
+    let octets = net.network().octets();
+    ip.network = net.to_string();
+    ip.net = octets[0] as i32;
+    ip.sub = octets[1] as i32;
+    ip.sub2 = octets[2] as i32;
+    ip.netmask = format!("{}/{}", octets[3], net.prefix_len());
+    ip.range_start = Some(u32::from(net.network()) as i64);
+    ip.range_end = Some(u32::from(net.broadcast()) as i64);
+    ip.geoname_id = record.geoname_id;
+    ip.registered_country_geoname_id = record.registered_country_geoname_id;
+    ip.represented_country_geoname_id = record.represented_country_geoname_id;
+    ip.latitude = record.latitude;
+    ip.longitude = record.longitude;
+    ip.accuracy_radius = record.accuracy_radius;
+
+    // The 2dsphere index rejects documents with invalid points, `spotme verify` reports those coordinates
+    if let (Ok(latitude), Ok(longitude)) = (ip.latitude.parse::<f64>(), ip.longitude.parse::<f64>()) {
+        if valid_coordinates(latitude, longitude) {
+            ip.centroid = Some(GeoPoint::new(latitude, longitude));
+        }
+    }
+
+    Ok(ip)
+}
+
+fn location_from_record(record: LocationRecord) -> Result<Location, String> {
+    let mut location = Location::new();
+    location.geoname_id = record.geoname_id;
+    location.continent_name = record.continent_name;
+    location.country_iso_code = record.country_iso_code;
+    location.country_name = record.country_name;
+    location.subdivision_1_name = record.subdivision_1_name;
+    location.subdivision_2_name = record.subdivision_2_name;
+    location.city_name = record.city_name;
+    location.time_zone = record.time_zone;
+
+    Ok(location)
+}
@@ -0,0 +1,113 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+use chrono::Utc;
+use ipnet::Ipv4Net;
+use crate::controllers::schema::{LookupResponse, ReservedResponse};
+use crate::lookup::{candidate_networks, find_location, find_right_entry, get_netmask_value, Locator, LookupError};
+use crate::models::ip::Ip;
+use crate::reserved::reserved_range;
+use crate::time_zone::time_zone_info;
+
+/// Looks `ip` up like `/v2/ip-lookup` does, printing how the answer was reached before the answer itself.
+pub fn run(locator: &Locator, ip: &str) -> Result<(), String> {
+    let addr = IpAddr::from_str(ip).map_err(|_| format!("{:?} is not a valid IP address", ip))?;
+
+    println!("Dataset: {}", locator.repos.dataset_version().unwrap_or_else(|| "(unversioned)".to_string()));
+    println!();
+
+    let network_override = locator.find_override(&addr);
+    match network_override {
+        Some((ref net, ref network_override)) => println!("Override: {} matches, it wins over the reserved ranges and the dataset (labels: {})", net, labels(&network_override.labels)),
+        None => println!("Override: none matches"),
+    }
+
+    match reserved_range(&addr) {
+        Some(range) => println!("Reserved: {} ({}, {})", range.network, range.purpose, range.reference),
+        None => println!("Reserved: no"),
+    }
+
+    match addr {
+        IpAddr::V4(addr_v4) => {
+            let candidates = candidate_networks(&locator.repos, &addr_v4).map_err(|e| format!("{:?}", e))?;
+            print_candidates(&addr, &candidates);
+
+            match find_right_entry(&candidates) {
+                Some(network) => {
+                    println!("Selected: {}, the candidate with the highest netmask score, malformed netmasks aside", network.network);
+
+                    if !contains(&network, &addr) {
+                        println!("    warning: {} is not inside {}, the octet index returned no better candidate", addr, network.network);
+                    }
+
+                    print_location_fallback(locator, &network)?;
+                }
+                None => println!("Selected: none, no candidate network"),
+            }
+        }
+        IpAddr::V6(_) => println!("Candidates: none, only IPv4 addresses are in the dataset"),
+    }
+
+    println!();
+
+    // The same path as the server, including overrides and reserved ranges
+    match locator.lookup(addr) {
+        Ok(result) => {
+            let mut lookup_response = LookupResponse::from(&result);
+            lookup_response.time_zone_details = time_zone_info(&result.location.time_zone, Utc::now());
+            println!("{}", serde_json::to_string_pretty(&lookup_response).expect("Will be ok"));
+            Ok(())
+        }
+        Err(LookupError::Reserved(range)) => {
+            println!("{}", serde_json::to_string_pretty(&ReservedResponse::new(addr, range)).expect("Will be ok"));
+            Ok(())
+        }
+        Err(e) => Err(format!("Lookup of {} failed: {:?}", addr, e)),
+    }
+}
+
+fn print_candidates(addr: &IpAddr, candidates: &[Ip]) {
+    println!("Candidates: {}", candidates.len());
+
+    for candidate in candidates {
+        println!("    {:<20} netmask {:<8} score {:<9} {}",
+                 candidate.network,
+                 candidate.netmask,
+                 get_netmask_value(&candidate.netmask).map_or_else(|| "malformed".to_string(), |score| score.to_string()),
+                 if contains(candidate, addr) { "contains the address" } else { "does not contain the address" });
+    }
+}
+
+/// Which geoname ids of the selected network were tried, in the order lookups try them.
+fn print_location_fallback(locator: &Locator, network: &Ip) -> Result<(), String> {
+    let geoname_ids = [
+        ("geoname_id", &network.geoname_id),
+        ("registered_country_geoname_id", &network.registered_country_geoname_id),
+        ("represented_country_geoname_id", &network.represented_country_geoname_id),
+    ];
+
+    for (field, geoname_id) in geoname_ids.iter() {
+        println!("    {}: {}", field, if geoname_id.is_empty() { "(empty)" } else { geoname_id.as_str() });
+    }
+
+    match find_location(&locator.repos, network).map_err(|e| format!("{:?}", e))? {
+        Some((location, source)) => println!("Location: geoname {} found through {:?}", location.geoname_id, source),
+        None => println!("Location: none of the geoname ids is in the location collection"),
+    }
+
+    Ok(())
+}
+
+fn contains(network: &Ip, addr: &IpAddr) -> bool {
+    match (network.network.parse::<Ipv4Net>(), addr) {
+        (Ok(net), IpAddr::V4(addr_v4)) => net.contains(addr_v4),
+        _ => false,
+    }
+}
+
+fn labels(labels: &[String]) -> String {
+    if labels.is_empty() {
+        "none".to_string()
+    } else {
+        labels.join(", ")
+    }
+}
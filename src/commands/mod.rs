@@ -0,0 +1,6 @@
+pub mod dataset;
+pub mod enrich;
+pub mod import;
+pub mod lookup;
+pub mod threats;
+pub mod verify;
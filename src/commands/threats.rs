@@ -0,0 +1,173 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::mem;
+use std::net::Ipv4Addr;
+use bson::Bson;
+use bson::oid::ObjectId;
+use ipnet::Ipv4Net;
+use crate::models::{Repository, RepositoryCollection};
+use crate::models::threat::{ThreatKind, ThreatRange};
+use crate::settings::ThreatsCommand;
+
+const BATCH_SIZE: usize = 10_000;
+
+pub fn run(repos: &RepositoryCollection, command: &ThreatsCommand) -> Result<(), String> {
+    match command {
+        ThreatsCommand::List => list(repos),
+        ThreatsCommand::Import { list, kind, file } => import(repos, list, kind, file),
+    }
+}
+
+fn list(repos: &RepositoryCollection) -> Result<(), String> {
+    let pipeline = vec![
+        doc! {"$group": { "_id": { "list": "$list", "kind": "$kind" }, "count": { "$sum": 1 } }},
+        doc! {"$sort": { "_id.list": 1 }},
+    ];
+
+    let lists = repos.threats.find_with_pipeline(pipeline, None).map_err(|e| format!("{:?}", e))?;
+
+    if lists.is_empty() {
+        println!("No threat list imported");
+    }
+
+    for document in lists {
+        let id = document.get_document("_id").map_err(|e| format!("{:?}", e))?;
+        let count = match document.get("count") {
+            Some(&Bson::I32(count)) => count as i64,
+            Some(&Bson::I64(count)) => count,
+            _ => 0,
+        };
+
+        println!("{} ({}): {} ranges", id.get_str("list").unwrap_or_default(), id.get_str("kind").unwrap_or_default(), count);
+    }
+
+    Ok(())
+}
+
+/// Replaces the ranges of `list` with the ones of `path`. The new ranges are written as a new generation,
+/// lookups keep matching the current one until the switch, after which it is deleted.
+fn import(repos: &RepositoryCollection, list: &str, kind: &str, path: &str) -> Result<(), String> {
+    let kind = match kind {
+        "tor" => ThreatKind::Tor,
+        "anonymous_proxy" => ThreatKind::AnonymousProxy,
+        "hosting" => ThreatKind::Hosting,
+        other => return Err(format!("Unknown kind of threat list {}", other)),
+    };
+
+    let file = File::open(path).map_err(|e| format!("Unable to open {}: {}", path, e))?;
+    let mut parsed = Vec::new();
+
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Unable to read {}: {}", path, e))?;
+
+        match parse_line(&line) {
+            Ok(Some(range)) => parsed.push(range),
+            Ok(None) => {}
+            Err(e) => warn!("Skipping line {} of {}: {}", index + 1, path, e),
+        }
+    }
+
+    let generation = ObjectId::new().map_err(|e| format!("{:?}", e))?.to_hex();
+    let ranges = merge_ranges(parsed).into_iter()
+        .map(|(start, end)| ThreatRange {
+            id: ObjectId::new().unwrap(),
+            list: list.to_string(),
+            generation: generation.clone(),
+            kind,
+            start: start as i64,
+            end: end as i64,
+        })
+        .collect::<Vec<ThreatRange>>();
+
+    let read = ranges.len() as i64;
+    let inserted = match insert_ranges(repos, ranges) {
+        Ok(inserted) => inserted,
+        Err(e) => {
+            let _ = repos.threats.delete_many(doc! {"list": list, "generation": generation.as_str()});
+            return Err(format!("Unable to import {}, {} was left unchanged: {}", path, list, e));
+        }
+    };
+
+    if inserted != read {
+        let _ = repos.threats.delete_many(doc! {"list": list, "generation": generation.as_str()});
+        return Err(format!("Only {} of the {} ranges of {} were written, {} was left unchanged", inserted, read, path, list));
+    }
+
+    repos.switch_threat_list(list, &generation).map_err(|e| format!("{:?}", e))?;
+    let deleted = repos.threats.delete_many(doc! {"list": list, "generation": { "$ne": generation.as_str() }}).map_err(|e| format!("{:?}", e))?;
+
+    println!("Replaced {} ranges of {} with {} ranges from {}", deleted, list, inserted, path);
+
+    Ok(())
+}
+
+/// Sorts the ranges and merges the overlapping and adjacent ones, so that lookups can rely on the ranges of a list never overlapping.
+fn merge_ranges(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort();
+
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if u64::from(start) <= u64::from(last.1) + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+fn insert_ranges(repos: &RepositoryCollection, ranges: Vec<ThreatRange>) -> Result<i64, String> {
+    let mut inserted = 0;
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+    for range in ranges {
+        batch.push(range);
+
+        if batch.len() >= BATCH_SIZE {
+            inserted += repos.threats.insert_all(mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE))).map_err(|e| format!("{:?}", e))?;
+        }
+    }
+
+    inserted += repos.threats.insert_all(batch).map_err(|e| format!("{:?}", e))?;
+
+    Ok(inserted)
+}
+
+/// Reads an address, a CIDR or a `start-end` range, ignoring comments.
+/// The `ExitAddress` lines of Tor's `exit-addresses` format are understood as well.
+fn parse_line(line: &str) -> Result<Option<(u32, u32)>, String> {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    let mut tokens = line.split_whitespace();
+
+    let value = match tokens.next() {
+        None => return Ok(None),
+        Some("ExitAddress") => tokens.next().ok_or_else(|| "ExitAddress without address".to_string())?,
+        Some("ExitNode") | Some("Published") | Some("LastStatus") => return Ok(None),
+        Some(value) => value,
+    };
+
+    if let Some(separator) = value.find('-') {
+        let start = parse_address(&value[..separator])?;
+        let end = parse_address(&value[separator + 1..])?;
+
+        if start > end {
+            return Err(format!("{} ends before it starts", value));
+        }
+
+        return Ok(Some((start, end)));
+    }
+
+    if value.contains('/') {
+        let net = value.parse::<Ipv4Net>().map_err(|_| format!("{} is not an IPv4 CIDR", value))?;
+        return Ok(Some((u32::from(net.network()), u32::from(net.broadcast()))));
+    }
+
+    let address = parse_address(value)?;
+    Ok(Some((address, address)))
+}
+
+fn parse_address(value: &str) -> Result<u32, String> {
+    value.trim().parse::<Ipv4Addr>()
+        .map(u32::from)
+        .map_err(|_| format!("{} is not an IPv4 address", value))
+}
@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+use ipnet::Ipv4Net;
+use crate::models::{Repository, RepositoryCollection};
+use crate::models::ip::Ip;
+
+/// Number of offending records printed per kind of problem
+const MAX_SAMPLES: usize = 20;
+
+struct Problems {
+    title: &'static str,
+    count: usize,
+    samples: Vec<String>,
+}
+
+impl Problems {
+    fn new(title: &'static str) -> Self {
+        Problems {
+            title,
+            count: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, sample: String) {
+        self.count += 1;
+
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(sample);
+        }
+    }
+
+    fn print(&self) {
+        println!("{}: {}", self.title, self.count);
+
+        for sample in &self.samples {
+            println!("    {}", sample);
+        }
+
+        if self.count > self.samples.len() {
+            println!("    ... and {} more", self.count - self.samples.len());
+        }
+    }
+}
+
+pub fn run(repos: &RepositoryCollection) -> Result<(), String> {
+    println!("Verifying dataset {}..", repos.dataset_version().unwrap_or_else(|| "(unversioned)".to_string()));
+
+    let mut undecodable = Problems::new("Undecodable documents");
+    let mut bad_locations = Problems::new("Locations without geoname_id");
+    let mut geoname_ids = HashSet::new();
+
+    repos.location.scan(None, |location| match location {
+        Ok(location) => {
+            if location.geoname_id.is_empty() {
+                bad_locations.add(format!("{}", location.id));
+            } else {
+                geoname_ids.insert(location.geoname_id);
+            }
+        }
+        Err(e) => undecodable.add(format!("location: {}", e)),
+    }).map_err(|e| format!("{:?}", e))?;
+
+    let mut orphans = Problems::new("Networks pointing to a missing location");
+    let mut malformed_networks = Problems::new("Malformed networks");
+    let mut malformed_netmasks = Problems::new("Netmask or octet fields not matching the network");
+    let mut bad_coordinates = Problems::new("Malformed or out of range coordinates");
+    let mut overlaps = Problems::new("Overlapping networks");
+    let mut without_geoname = 0;
+    let mut ranges = Vec::new();
+
+    repos.ip.scan(None, |ip| {
+        let ip = match ip {
+            Ok(ip) => ip,
+            Err(e) => return undecodable.add(format!("ip: {}", e)),
+        };
+
+        let referenced = [&ip.geoname_id, &ip.registered_country_geoname_id, &ip.represented_country_geoname_id];
+
+        if referenced.iter().all(|geoname_id| geoname_id.is_empty()) {
+            without_geoname += 1;
+        }
+
+        for geoname_id in referenced.iter().filter(|geoname_id| !geoname_id.is_empty()) {
+            if !geoname_ids.contains(*geoname_id) {
+                orphans.add(format!("{} -> {}", ip.network, geoname_id));
+            }
+        }
+
+        if let Err(e) = check_coordinates(&ip) {
+            bad_coordinates.add(format!("{}: {}", ip.network, e));
+        }
+
+        match ip.network.parse::<Ipv4Net>() {
+            Ok(net) if net != net.trunc() => malformed_networks.add(format!("{}: host bits are set", ip.network)),
+            Ok(net) => {
+                if !octet_fields_match(&ip, &net) {
+                    malformed_netmasks.add(format!("{}: net={} sub={} sub2={} netmask={}", ip.network, ip.net, ip.sub, ip.sub2, ip.netmask));
+                }
+
+                ranges.push((u32::from(net.network()), u32::from(net.broadcast()), ip.network));
+            }
+            Err(e) => malformed_networks.add(format!("{}: {}", ip.network, e)),
+        }
+    }).map_err(|e| format!("{:?}", e))?;
+
+    // Sweep the networks by start address, a network starting before the furthest end seen so far overlaps it
+    ranges.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    let mut furthest: Option<(u32, &str)> = None;
+
+    for (start, end, network) in &ranges {
+        match furthest {
+            Some((furthest_end, furthest_network)) if *start <= furthest_end => {
+                overlaps.add(format!("{} overlaps {}", network, furthest_network));
+
+                if *end > furthest_end {
+                    furthest = Some((*end, network.as_str()));
+                }
+            }
+            _ => furthest = Some((*end, network.as_str())),
+        }
+    }
+
+    println!("Checked {} locations and {} networks", geoname_ids.len(), ranges.len());
+
+    if without_geoname > 0 {
+        println!("Networks without any geoname_id (not an error): {}", without_geoname);
+    }
+
+    let all_problems = [undecodable, bad_locations, orphans, malformed_networks, malformed_netmasks, bad_coordinates, overlaps];
+    let total = all_problems.iter().map(|problems| problems.count).sum::<usize>();
+
+    for problems in all_problems.iter().filter(|problems| problems.count > 0) {
+        problems.print();
+    }
+
+    if total > 0 {
+        Err(format!("Found {} problems", total))
+    } else {
+        println!("No problems found");
+        Ok(())
+    }
+}
+
+/// GeoLite2 leaves the coordinates of some networks empty, only present values are checked.
+fn check_coordinates(ip: &Ip) -> Result<(), String> {
+    if !ip.latitude.is_empty() {
+        let latitude = ip.latitude.parse::<f64>().map_err(|_| format!("latitude {:?}", ip.latitude))?;
+
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(format!("latitude {} out of range", latitude));
+        }
+    }
+
+    if !ip.longitude.is_empty() {
+        let longitude = ip.longitude.parse::<f64>().map_err(|_| format!("longitude {:?}", ip.longitude))?;
+
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(format!("longitude {} out of range", longitude));
+        }
+    }
+
+    Ok(())
+}
+
+/// The lookup relies on `net`, `sub`, `sub2` and `netmask` being derived from `network`
+fn octet_fields_match(ip: &Ip, net: &Ipv4Net) -> bool {
+    let octets = net.network().octets();
+
+    ip.net == octets[0] as i32
+        && ip.sub == octets[1] as i32
+        && ip.sub2 == octets[2] as i32
+        && ip.netmask == format!("{}/{}", octets[3], net.prefix_len())
+}
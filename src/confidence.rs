@@ -0,0 +1,194 @@
+use ipnet::IpNet;
+use crate::lookup::{LocationSource, LookupResult};
+
+/// Largest accuracy radius, in kilometers, at which a city is trusted
+const CITY_MAX_RADIUS_KM: u32 = 100;
+/// Largest accuracy radius, in kilometers, at which a subdivision is trusted
+const SUBDIVISION_MAX_RADIUS_KM: u32 = 500;
+
+/// Most precise level a location can be trusted at.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    City,
+    Subdivision,
+    Country,
+    Continent,
+    Unknown,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Confidence {
+    /// From 0 to 100
+    pub score: u8,
+    pub granularity: Granularity,
+    pub accuracy_radius_km: Option<u32>,
+    /// Prefix length of the matching network
+    pub prefix_length: Option<u8>,
+    /// Whether the location has a city, as opposed to country level data only
+    pub city_level: bool,
+}
+
+/// Combines the accuracy radius, the size of the matching network and the precision of the location.
+/// Overrides are set by operators and always fully trusted.
+pub fn confidence(result: &LookupResult) -> Confidence {
+    let accuracy_radius_km = result.ip.accuracy_radius.parse::<u32>().ok();
+    let prefix_length = result.ip.network.parse::<IpNet>().ok().map(|net| net.prefix_len());
+    let precise_source = result.location_source == LocationSource::Geoname || result.location_source == LocationSource::Override;
+    let city_level = precise_source && !result.location.city_name.is_empty();
+
+    if result.location_source == LocationSource::Override {
+        return Confidence {
+            score: 100,
+            granularity: granularity(result, city_level, true),
+            accuracy_radius_km,
+            prefix_length,
+            city_level,
+        };
+    }
+
+    let radius_score = match accuracy_radius_km {
+        None => 0.3,
+        Some(radius) if radius <= 10 => 1.0,
+        Some(radius) if radius <= 50 => 0.8,
+        Some(radius) if radius <= CITY_MAX_RADIUS_KM => 0.6,
+        Some(radius) if radius <= SUBDIVISION_MAX_RADIUS_KM => 0.4,
+        Some(radius) if radius <= 1000 => 0.2,
+        Some(_) => 0.1,
+    };
+
+    // Large blocks are usually allocations geolocated as a whole
+    let prefix_score = match prefix_length {
+        None => 0.5,
+        Some(prefix_length) if prefix_length >= 24 => 1.0,
+        Some(prefix_length) if prefix_length >= 20 => 0.8,
+        Some(prefix_length) if prefix_length >= 16 => 0.6,
+        Some(prefix_length) if prefix_length >= 12 => 0.4,
+        Some(_) => 0.2,
+    };
+
+    let city_score = if city_level { 1.0 } else { 0.0 };
+    let source_factor = if precise_source { 1.0 } else { 0.5 };
+    let score = (0.5 * radius_score + 0.3 * prefix_score + 0.2 * city_score) * source_factor;
+
+    Confidence {
+        score: (score * 100.0).round() as u8,
+        granularity: granularity(
+            result,
+            city_level && accuracy_radius_km.map_or(false, |radius| radius <= CITY_MAX_RADIUS_KM),
+            precise_source && accuracy_radius_km.map_or(false, |radius| radius <= SUBDIVISION_MAX_RADIUS_KM),
+        ),
+        accuracy_radius_km,
+        prefix_length,
+        city_level,
+    }
+}
+
+fn granularity(result: &LookupResult, trust_city: bool, trust_subdivision: bool) -> Granularity {
+    let location = &result.location;
+
+    if trust_city && !location.city_name.is_empty() {
+        Granularity::City
+    } else if trust_subdivision && !location.subdivision_1_name.is_empty() {
+        Granularity::Subdivision
+    } else if !location.country_name.is_empty() {
+        Granularity::Country
+    } else if !location.continent_name.is_empty() {
+        Granularity::Continent
+    } else {
+        Granularity::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ip::Ip;
+    use crate::models::location::Location;
+    use crate::threats::ThreatFlags;
+
+    fn result(network: &str, accuracy_radius: &str, location_source: LocationSource, city_name: &str) -> LookupResult {
+        let mut ip = Ip::new();
+        ip.network = network.to_string();
+        ip.accuracy_radius = accuracy_radius.to_string();
+
+        let mut location = Location::new();
+        location.continent_name = "Europe".to_string();
+        location.country_name = "France".to_string();
+        location.subdivision_1_name = "Île-de-France".to_string();
+        location.city_name = city_name.to_string();
+
+        LookupResult {
+            request_ip: "192.0.2.1".parse().unwrap(),
+            ip,
+            location,
+            location_source,
+            dataset_version: None,
+            labels: Vec::new(),
+            threats: ThreatFlags::default(),
+        }
+    }
+
+    #[test]
+    fn scores_the_accuracy_radius_at_its_bounds() {
+        let scores = ["10", "11", "50", "51", "100", "101", "500", "501", "1000", "1001", ""].iter()
+            .map(|radius| confidence(&result("192.0.2.0/24", radius, LocationSource::Geoname, "")).score)
+            .collect::<Vec<u8>>();
+
+        assert_eq!(scores, vec![80, 70, 70, 60, 60, 50, 50, 40, 40, 35, 45]);
+    }
+
+    #[test]
+    fn scores_the_prefix_length_at_its_bounds() {
+        let scores = ["192.0.2.0/24", "192.0.2.0/23", "192.0.0.0/20", "192.0.0.0/19", "192.0.0.0/16", "192.0.0.0/15", "192.0.0.0/12", "192.0.0.0/11", "not a network"].iter()
+            .map(|network| confidence(&result(network, "5", LocationSource::Geoname, "")).score)
+            .collect::<Vec<u8>>();
+
+        assert_eq!(scores, vec![80, 74, 74, 68, 68, 62, 62, 56, 65]);
+    }
+
+    #[test]
+    fn trusts_the_city_within_its_radius_only() {
+        let precise = confidence(&result("192.0.2.0/24", "5", LocationSource::Geoname, "Paris"));
+        assert_eq!(precise.score, 100);
+        assert_eq!(precise.granularity, Granularity::City);
+        assert!(precise.city_level);
+
+        assert_eq!(confidence(&result("192.0.2.0/24", "100", LocationSource::Geoname, "Paris")).granularity, Granularity::City);
+        assert_eq!(confidence(&result("192.0.2.0/24", "101", LocationSource::Geoname, "Paris")).granularity, Granularity::Subdivision);
+        assert_eq!(confidence(&result("192.0.2.0/24", "500", LocationSource::Geoname, "Paris")).granularity, Granularity::Subdivision);
+        assert_eq!(confidence(&result("192.0.2.0/24", "501", LocationSource::Geoname, "Paris")).granularity, Granularity::Country);
+        assert_eq!(confidence(&result("192.0.2.0/24", "", LocationSource::Geoname, "Paris")).granularity, Granularity::Country);
+    }
+
+    #[test]
+    fn country_fallbacks_are_halved_and_never_city_level() {
+        let fallback = confidence(&result("192.0.2.0/24", "5", LocationSource::RegisteredCountry, "Paris"));
+
+        assert_eq!(fallback.score, 40);
+        assert_eq!(fallback.granularity, Granularity::Country);
+        assert!(!fallback.city_level);
+    }
+
+    #[test]
+    fn falls_back_on_the_continent_then_unknown() {
+        let mut continent_only = result("192.0.2.0/24", "5", LocationSource::Geoname, "");
+        continent_only.location.country_name.clear();
+        continent_only.location.subdivision_1_name.clear();
+        assert_eq!(confidence(&continent_only).granularity, Granularity::Continent);
+
+        continent_only.location.continent_name.clear();
+        assert_eq!(confidence(&continent_only).granularity, Granularity::Unknown);
+    }
+
+    #[test]
+    fn overrides_are_fully_trusted() {
+        let overridden = confidence(&result("10.0.0.0/8", "1001", LocationSource::Override, "Paris"));
+
+        assert_eq!(overridden.score, 100);
+        assert_eq!(overridden.granularity, Granularity::City);
+        assert_eq!(overridden.accuracy_radius_km, Some(1001));
+        assert_eq!(overridden.prefix_length, Some(8));
+        assert!(overridden.city_level);
+    }
+}
@@ -0,0 +1,533 @@
+use saphir::*;
+use saphir::Method;
+use bson::Document;
+use bson::oid::ObjectId;
+use chrono_tz::Tz;
+use ipnet::Ipv4Net;
+use mongodb::coll::options::FindOptions;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use crate::auth::ApiKeyAuthenticator;
+use crate::geo::valid_coordinates;
+use crate::lookup::Locator;
+use crate::models::Repository;
+use crate::models::ip::{GeoPoint, Ip};
+use crate::models::location::Location;
+use crate::models::network_override::NetworkOverride;
+use crate::overrides::validate_override;
+use crate::rate_limit::RateLimiter;
+use super::helpers::{query_param, page_params, json_response, error_response, authenticate_admin, rate_limit_client, add_routes, Route};
+
+pub struct AdminController {
+    dispatch: ControllerDispatch<Locator>,
+    auth: ApiKeyAuthenticator,
+    limiter: RateLimiter,
+}
+
+impl AdminController {
+    /// Every route of the controller, the OpenAPI document is tested against this table.
+    pub const ROUTES: &'static [Route<Locator>] = &[
+        Route { method: Method::GET, path: "/admin/overrides", handler: list_overrides },
+        Route { method: Method::POST, path: "/admin/overrides", handler: create_override },
+        Route { method: Method::GET, path: "/admin/overrides/{id}", handler: get_override },
+        Route { method: Method::PUT, path: "/admin/overrides/{id}", handler: update_override },
+        Route { method: Method::DELETE, path: "/admin/overrides/{id}", handler: delete_override },
+        Route { method: Method::GET, path: "/admin/ip", handler: list_ips },
+        Route { method: Method::POST, path: "/admin/ip", handler: create_ip },
+        Route { method: Method::GET, path: "/admin/ip/{id}", handler: get_ip },
+        Route { method: Method::PUT, path: "/admin/ip/{id}", handler: update_ip },
+        Route { method: Method::DELETE, path: "/admin/ip/{id}", handler: delete_ip },
+        Route { method: Method::GET, path: "/admin/location", handler: list_locations },
+        Route { method: Method::POST, path: "/admin/location", handler: create_location },
+        Route { method: Method::GET, path: "/admin/location/{id}", handler: get_location },
+        Route { method: Method::PUT, path: "/admin/location/{id}", handler: update_location },
+        Route { method: Method::DELETE, path: "/admin/location/{id}", handler: delete_location },
+    ];
+
+    pub fn new(locator: Locator, auth: ApiKeyAuthenticator, limiter: RateLimiter) -> Self {
+        let dispatch = ControllerDispatch::new(locator);
+        add_routes(&dispatch, "/admin/", Self::ROUTES);
+
+        AdminController {
+            dispatch,
+            auth,
+            limiter,
+        }
+    }
+}
+
+impl Controller for AdminController {
+    fn handle(&self, req: &mut SyncRequest, res: &mut SyncResponse) {
+        // Shares the per address buckets of the lookup controller, so admin keys cannot be guessed at full speed
+        if rate_limit_client(&self.limiter, req, res).is_err() {
+            return;
+        }
+
+        if authenticate_admin(&self.auth, req, res).is_err() {
+            return;
+        }
+
+        self.dispatch.dispatch(req, res);
+    }
+
+    fn base_path(&self) -> &str {
+        "^/admin/"
+    }
+}
+
+/// Body of the `/admin/ip` writes, the octet, netmask and range fields are derived from `network`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct IpBody {
+    network: String,
+    geoname_id: String,
+    registered_country_geoname_id: String,
+    represented_country_geoname_id: String,
+    latitude: String,
+    longitude: String,
+    accuracy_radius: String,
+}
+
+/// Body of the `/admin/location` writes.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LocationBody {
+    geoname_id: String,
+    continent_name: String,
+    country_iso_code: String,
+    country_name: String,
+    subdivision_1_name: String,
+    subdivision_2_name: String,
+    city_name: String,
+    time_zone: String,
+}
+
+/// Models are stored with a BSON `_id`, exposed as a plain hexadecimal `id`.
+fn model_json<T: Serialize>(id: &ObjectId, model: &T) -> Value {
+    let mut value = serde_json::to_value(model).expect("Will be ok");
+
+    if let Value::Object(ref mut fields) = value {
+        fields.remove("_id");
+        fields.insert("id".to_string(), Value::String(id.to_hex()));
+    }
+
+    value
+}
+
+/// Id of the record targeted by the request, the routes only match 24 hexadecimal digits.
+fn path_id(req: &SyncRequest, record: &str, res: &mut SyncResponse) -> Option<ObjectId> {
+    let id = req.uri().path().rsplit('/').next().and_then(|id| ObjectId::with_string(id).ok());
+
+    if id.is_none() {
+        error_response(res, StatusCode::NOT_FOUND, "not_found", &format!("No {} has this id.", record));
+    }
+
+    id
+}
+
+/// Reads the JSON body of the request, writing the 400 response when it cannot be decoded.
+fn body_model<T: DeserializeOwned>(req: &SyncRequest, res: &mut SyncResponse) -> Option<T> {
+    match serde_json::from_slice::<T>(req.body()) {
+        Ok(model) => Some(model),
+        Err(e) => {
+            error_response(res, StatusCode::BAD_REQUEST, "invalid_body", &format!("The request body is invalid: {}", e));
+            None
+        }
+    }
+}
+
+/// Filter made of the given query parameters, mapped to the fields of the collection.
+fn query_filter(req: &SyncRequest, fields: &[(&str, &str)]) -> Document {
+    let mut filter = Document::new();
+
+    for (param, field) in fields {
+        if let Some(value) = query_param(req, param) {
+            filter.insert(*field, value);
+        }
+    }
+
+    filter
+}
+
+/// Writes a page of the records of `repository` matching `filter`, listed under the `name` key.
+fn list_response<R, F>(repository: &R, filter: Document, sort: Document, name: &str, model_id: F, req: &SyncRequest, res: &mut SyncResponse)
+    where R: Repository, R::Model: Serialize + ::serde::Deserialize<'static>, F: Fn(&R::Model) -> ObjectId {
+    let (page, per_page) = match page_params(req, 100, 1000) {
+        Ok(params) => params,
+        Err(message) => return error_response(res, StatusCode::BAD_REQUEST, "invalid_page", &message),
+    };
+
+    let mut options = FindOptions::new();
+    options.sort = Some(sort);
+    options.skip = Some((page - 1) * per_page);
+    options.limit = Some(per_page);
+
+    let result = repository.count(Some(filter.clone()))
+        .and_then(|total| Ok((total, repository.find_with_options(filter, options)?)));
+
+    match result {
+        Ok((total, models)) => {
+            let mut list_json = json!({
+                "total": total,
+                "page": page,
+                "per_page": per_page,
+            });
+            list_json[name] = Value::Array(models.iter().map(|model| model_json(&model_id(model), model)).collect());
+
+            json_response(res, StatusCode::OK, &list_json)
+        }
+        Err(e) => {
+            error!("Unable to list the {}: {:?}", name, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The records could not be listed.")
+        }
+    }
+}
+
+fn get_record<R>(repository: &R, record: &str, req: &SyncRequest, res: &mut SyncResponse)
+    where R: Repository, R::Model: Serialize + ::serde::Deserialize<'static> {
+    let id = match path_id(req, record, res) {
+        Some(id) => id,
+        None => return,
+    };
+
+    match repository.get_by_id(id.clone()) {
+        Ok(Some(model)) => json_response(res, StatusCode::OK, &model_json(&id, &model)),
+        Ok(None) => error_response(res, StatusCode::NOT_FOUND, "not_found", &format!("No {} has this id.", record)),
+        Err(e) => {
+            error!("Unable to read the {} {}: {:?}", record, id, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", &format!("The {} could not be read.", record))
+        }
+    }
+}
+
+/// Returns whether the record was created.
+fn insert_record<R>(repository: &R, record: &str, id: &ObjectId, model: R::Model, res: &mut SyncResponse) -> bool
+    where R: Repository, R::Model: Serialize {
+    let model_json = model_json(id, &model);
+
+    match repository.insert(model) {
+        Ok(_) => {
+            json_response(res, StatusCode::CREATED, &model_json);
+            true
+        }
+        Err(e) => {
+            error!("Unable to create the {}: {:?}", record, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", &format!("The {} could not be created.", record));
+            false
+        }
+    }
+}
+
+/// Returns whether the record was replaced.
+fn replace_record<R>(repository: &R, record: &str, id: &ObjectId, model: R::Model, res: &mut SyncResponse) -> bool
+    where R: Repository, R::Model: Serialize {
+    let model_json = model_json(id, &model);
+
+    match repository.update_by_id(id.clone(), model) {
+        Ok(ref result) if result.matched_count == 0 => {
+            error_response(res, StatusCode::NOT_FOUND, "not_found", &format!("No {} has this id.", record));
+            false
+        }
+        Ok(_) => {
+            json_response(res, StatusCode::OK, &model_json);
+            true
+        }
+        Err(e) => {
+            error!("Unable to update the {} {}: {:?}", record, id, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", &format!("The {} could not be updated.", record));
+            false
+        }
+    }
+}
+
+/// Returns whether the record was deleted.
+fn delete_record<R>(repository: &R, record: &str, req: &SyncRequest, res: &mut SyncResponse) -> bool
+    where R: Repository, R::Model: ::serde::Deserialize<'static> {
+    let id = match path_id(req, record, res) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    let deleted = repository.get_by_id(id.clone())
+        .and_then(|model| match model {
+            Some(_) => repository.delete_by_id(id.clone()).map(|_| true),
+            None => Ok(false),
+        });
+
+    match deleted {
+        Ok(true) => {
+            res.status(StatusCode::NO_CONTENT);
+            true
+        }
+        Ok(false) => {
+            error_response(res, StatusCode::NOT_FOUND, "not_found", &format!("No {} has this id.", record));
+            false
+        }
+        Err(e) => {
+            error!("Unable to delete the {} {}: {:?}", record, id, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", &format!("The {} could not be deleted.", record));
+            false
+        }
+    }
+}
+
+/// Lookups are answered from memory, so they are refreshed after every write.
+fn reload_overrides(locator: &Locator) {
+    if let Err(e) = locator.reload_overrides() {
+        warn!("Unable to reload the network overrides: {:?}", e);
+    }
+}
+
+/// Other instances only learn about network and location writes through the data revision.
+fn data_changed(locator: &Locator) {
+    if let Err(e) = locator.data_changed() {
+        warn!("Unable to record the data revision, other instances keep their cached lookups: {:?}", e);
+    }
+}
+
+fn list_overrides(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let filter = query_filter(req, &[("label", "labels")]);
+
+    list_response(&locator.repos.overrides, filter, doc! {"network": 1, "_id": 1}, "overrides", |network_override: &NetworkOverride| network_override.id.clone(), req, res)
+}
+
+/// Reads and validates the override of the request body, writing the 400/422 response when it is invalid.
+fn override_body(req: &SyncRequest, res: &mut SyncResponse) -> Option<NetworkOverride> {
+    let mut network_override = body_model::<NetworkOverride>(req, res)?;
+
+    match validate_override(&network_override) {
+        Ok(net) => {
+            network_override.network = net.to_string();
+            Some(network_override)
+        }
+        Err(message) => {
+            error_response(res, StatusCode::UNPROCESSABLE_ENTITY, "invalid_override", &message);
+            None
+        }
+    }
+}
+
+fn create_override(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    if let Some(network_override) = override_body(req, res) {
+        if insert_record(&locator.repos.overrides, "override", &network_override.id.clone(), network_override, res) {
+            reload_overrides(locator);
+        }
+    }
+}
+
+fn get_override(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    get_record(&locator.repos.overrides, "override", req, res)
+}
+
+fn update_override(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let id = match path_id(req, "override", res) {
+        Some(id) => id,
+        None => return,
+    };
+
+    if let Some(mut network_override) = override_body(req, res) {
+        network_override.id = id.clone();
+
+        if replace_record(&locator.repos.overrides, "override", &id, network_override, res) {
+            reload_overrides(locator);
+        }
+    }
+}
+
+fn delete_override(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    if delete_record(&locator.repos.overrides, "override", req, res) {
+        reload_overrides(locator);
+    }
+}
+
+/// Builds the network record of the request body, checking the CIDR and the coordinates.
+fn ip_from_body(body: IpBody) -> Result<Ip, String> {
+    let net = body.network.parse::<Ipv4Net>().map_err(|_| format!("{:?} is not a valid IPv4 CIDR", body.network))?;
+
+    if net != net.trunc() {
+        return Err(format!("{} has host bits set, use {}", net, net.trunc()));
+    }
+
+    let mut ip = Ip::new();
+    let octets = net.network().octets();
+    ip.network = net.to_string();
+    ip.net = octets[0] as i32;
+    ip.sub = octets[1] as i32;
+    ip.sub2 = octets[2] as i32;
+    ip.netmask = format!("{}/{}", octets[3], net.prefix_len());
+    ip.range_start = Some(u32::from(net.network()) as i64);
+    ip.range_end = Some(u32::from(net.broadcast()) as i64);
+
+    if !body.latitude.is_empty() || !body.longitude.is_empty() {
+        let coordinates = (body.latitude.parse::<f64>(), body.longitude.parse::<f64>());
+
+        match coordinates {
+            (Ok(latitude), Ok(longitude)) if valid_coordinates(latitude, longitude) => ip.centroid = Some(GeoPoint::new(latitude, longitude)),
+            _ => return Err(format!("{:?},{:?} are not valid coordinates", body.latitude, body.longitude)),
+        }
+    }
+
+    if !body.accuracy_radius.is_empty() && body.accuracy_radius.parse::<u32>().is_err() {
+        return Err(format!("{:?} is not a valid accuracy radius", body.accuracy_radius));
+    }
+
+    ip.geoname_id = body.geoname_id;
+    ip.registered_country_geoname_id = body.registered_country_geoname_id;
+    ip.represented_country_geoname_id = body.represented_country_geoname_id;
+    ip.latitude = body.latitude;
+    ip.longitude = body.longitude;
+    ip.accuracy_radius = body.accuracy_radius;
+
+    Ok(ip)
+}
+
+fn ip_body(req: &SyncRequest, res: &mut SyncResponse) -> Option<Ip> {
+    match ip_from_body(body_model::<IpBody>(req, res)?) {
+        Ok(ip) => Some(ip),
+        Err(message) => {
+            error_response(res, StatusCode::UNPROCESSABLE_ENTITY, "invalid_ip", &message);
+            None
+        }
+    }
+}
+
+// The `ip` and `location` endpoints read and write the collections of the active dataset
+
+fn list_ips(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let filter = query_filter(req, &[("network", "network"), ("geoname_id", "geoname_id")]);
+
+    list_response(&locator.repos.ip, filter, doc! {"net": 1, "sub": 1, "sub2": 1, "range_start": 1, "_id": 1}, "ips", |ip: &Ip| ip.id.clone(), req, res)
+}
+
+fn create_ip(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    if let Some(ip) = ip_body(req, res) {
+        if insert_record(&locator.repos.ip, "ip", &ip.id.clone(), ip, res) {
+            data_changed(locator);
+        }
+    }
+}
+
+fn get_ip(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    get_record(&locator.repos.ip, "ip", req, res)
+}
+
+fn update_ip(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let id = match path_id(req, "ip", res) {
+        Some(id) => id,
+        None => return,
+    };
+
+    if let Some(mut ip) = ip_body(req, res) {
+        ip.id = id.clone();
+
+        if replace_record(&locator.repos.ip, "ip", &id, ip, res) {
+            data_changed(locator);
+        }
+    }
+}
+
+fn delete_ip(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    if delete_record(&locator.repos.ip, "ip", req, res) {
+        data_changed(locator);
+    }
+}
+
+fn location_from_body(body: LocationBody) -> Result<Location, String> {
+    if body.geoname_id.is_empty() {
+        return Err("geoname_id is required".to_string());
+    }
+
+    if !body.country_iso_code.is_empty() && (body.country_iso_code.len() != 2 || !body.country_iso_code.chars().all(|c| c.is_ascii_alphabetic())) {
+        return Err(format!("{:?} is not an ISO 3166-1 alpha-2 code", body.country_iso_code));
+    }
+
+    if !body.time_zone.is_empty() && body.time_zone.parse::<Tz>().is_err() {
+        return Err(format!("{:?} is not an IANA time zone", body.time_zone));
+    }
+
+    let mut location = Location::new();
+    location.geoname_id = body.geoname_id;
+    location.continent_name = body.continent_name;
+    location.country_iso_code = body.country_iso_code.to_uppercase();
+    location.country_name = body.country_name;
+    location.subdivision_1_name = body.subdivision_1_name;
+    location.subdivision_2_name = body.subdivision_2_name;
+    location.city_name = body.city_name;
+    location.time_zone = body.time_zone;
+
+    Ok(location)
+}
+
+/// Reads and validates the location of the request body, a `geoname_id` can only be used by one location.
+fn location_body(locator: &Locator, id: Option<&ObjectId>, req: &SyncRequest, res: &mut SyncResponse) -> Option<Location> {
+    let location = match location_from_body(body_model::<LocationBody>(req, res)?) {
+        Ok(location) => location,
+        Err(message) => {
+            error_response(res, StatusCode::UNPROCESSABLE_ENTITY, "invalid_location", &message);
+            return None;
+        }
+    };
+
+    let mut duplicate_filter = doc! {"geoname_id": location.geoname_id.as_str()};
+
+    if let Some(id) = id {
+        duplicate_filter.insert("_id", doc! {"$ne": id.clone()});
+    }
+
+    match locator.repos.location.count(Some(duplicate_filter)) {
+        Ok(0) => Some(location),
+        Ok(_) => {
+            error_response(res, StatusCode::CONFLICT, "duplicate_geoname_id", &format!("Another location has the geoname_id {}.", location.geoname_id));
+            None
+        }
+        Err(e) => {
+            error!("Unable to check the geoname_id {}: {:?}", location.geoname_id, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The location could not be validated.");
+            None
+        }
+    }
+}
+
+fn list_locations(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let mut filter = query_filter(req, &[("geoname_id", "geoname_id"), ("city", "city_name")]);
+
+    if let Some(country) = query_param(req, "country") {
+        filter.insert("country_iso_code", country.to_uppercase());
+    }
+
+    list_response(&locator.repos.location, filter, doc! {"geoname_id": 1, "_id": 1}, "locations", |location: &Location| location.id.clone(), req, res)
+}
+
+fn create_location(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    if let Some(location) = location_body(locator, None, req, res) {
+        if insert_record(&locator.repos.location, "location", &location.id.clone(), location, res) {
+            data_changed(locator);
+        }
+    }
+}
+
+fn get_location(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    get_record(&locator.repos.location, "location", req, res)
+}
+
+fn update_location(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let id = match path_id(req, "location", res) {
+        Some(id) => id,
+        None => return,
+    };
+
+    if let Some(mut location) = location_body(locator, Some(&id), req, res) {
+        location.id = id.clone();
+
+        if replace_record(&locator.repos.location, "location", &id, location, res) {
+            data_changed(locator);
+        }
+    }
+}
+
+fn delete_location(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    if delete_record(&locator.repos.location, "location", req, res) {
+        data_changed(locator);
+    }
+}
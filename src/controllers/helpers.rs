@@ -0,0 +1,190 @@
+use std::net::IpAddr;
+use saphir::*;
+use saphir::Method;
+use serde::Serialize;
+use crate::auth::{ApiKeyAuthenticator, AuthError};
+use crate::geo::valid_coordinates;
+use crate::rate_limit::RateLimiter;
+use crate::models::api_key::ApiKey;
+
+/// A route of a controller: registered with its dispatcher and described under `path` in the OpenAPI document.
+pub struct Route<C: 'static> {
+    pub method: Method,
+    /// Absolute OpenAPI path, where `{id}` stands for an ObjectId
+    pub path: &'static str,
+    pub handler: fn(&C, &SyncRequest, &mut SyncResponse),
+}
+
+/// Registers `routes` on the dispatcher of a controller whose base path matches `prefix`.
+pub fn add_routes<C>(dispatch: &ControllerDispatch<C>, prefix: &str, routes: &[Route<C>]) {
+    for route in routes {
+        dispatch.add(route.method.clone(), reg!(&route_pattern(prefix, route.path)), route.handler);
+    }
+}
+
+/// Pattern matching `path` once the dispatcher has stripped `prefix`.
+fn route_pattern(prefix: &str, path: &str) -> String {
+    assert!(path.starts_with(prefix), "{} is not under {}", path, prefix);
+
+    format!("^{}$", path[prefix.len()..].replace('.', r"\.").replace("{id}", "[0-9a-fA-F]{24}"))
+}
+
+pub fn query_params(req: &SyncRequest) -> Vec<(String, String)> {
+    req.uri().query()
+        .and_then(|query| serde_urlencoded::from_str::<Vec<(String, String)>>(query).ok())
+        .unwrap_or_else(Vec::new)
+}
+
+pub fn query_param(req: &SyncRequest, name: &str) -> Option<String> {
+    query_params(req).into_iter().find(|(key, _)| key == name).map(|(_, value)| value)
+}
+
+/// Whether an optional boolean query parameter is set, e.g. `?tz=true`.
+pub fn flag_param(req: &SyncRequest, name: &str) -> bool {
+    match query_param(req, name) {
+        Some(value) => ["true", "1", "yes"].contains(&value.to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+/// Reads a latitude and a longitude from the query, in decimal degrees.
+pub fn coordinate_params(req: &SyncRequest, latitude_name: &str, longitude_name: &str) -> Result<(f64, f64), String> {
+    let latitude = query_param(req, latitude_name).and_then(|latitude| latitude.parse::<f64>().ok());
+    let longitude = query_param(req, longitude_name).and_then(|longitude| longitude.parse::<f64>().ok());
+
+    match (latitude, longitude) {
+        (Some(latitude), Some(longitude)) if valid_coordinates(latitude, longitude) => Ok((latitude, longitude)),
+        _ => Err(format!("`{}` must be between -90 and 90 and `{}` between -180 and 180.", latitude_name, longitude_name)),
+    }
+}
+
+/// Reads the 1-based `page` and `per_page` query parameters.
+pub fn page_params(req: &SyncRequest, default_per_page: i64, max_per_page: i64) -> Result<(i64, i64), String> {
+    let page = match query_param(req, "page").map(|page| page.parse::<i64>()) {
+        None => 1,
+        Some(Ok(page)) if page >= 1 => page,
+        _ => return Err("`page` must be a positive integer.".to_string()),
+    };
+
+    let per_page = match query_param(req, "per_page").map(|per_page| per_page.parse::<i64>()) {
+        None => default_per_page,
+        Some(Ok(per_page)) if per_page >= 1 && per_page <= max_per_page => per_page,
+        _ => return Err(format!("`per_page` must be an integer between 1 and {}.", max_per_page)),
+    };
+
+    // The number of records skipped must fit the database's integers
+    if (page - 1).checked_mul(per_page).is_none() {
+        return Err("`page` is too large.".to_string());
+    }
+
+    Ok((page, per_page))
+}
+
+pub fn json_response<T: Serialize>(res: &mut SyncResponse, status: StatusCode, body: &T) {
+    res.status(status);
+    res.header("Content-Type", "application/json");
+    res.body(serde_json::to_string(body).expect("Will be ok"));
+}
+
+pub fn error_response(res: &mut SyncResponse, status: StatusCode, code: &str, message: &str) {
+    let error_json = json!({
+        "error": {
+            "code": code,
+            "message": message,
+        }
+    });
+
+    json_response(res, status, &error_json);
+}
+
+/// Reads the API key from the configured header, falling back to the `api_key` query parameter.
+pub fn request_api_key(req: &SyncRequest, header: &str) -> Option<String> {
+    req.headers_map().get(header)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+        .or_else(|| query_param(req, "api_key"))
+}
+
+/// Writes the 401/429/500 response and returns `Err` when the request must not be dispatched.
+pub fn authenticate(auth: &ApiKeyAuthenticator, req: &SyncRequest, res: &mut SyncResponse) -> Result<Option<ApiKey>, ()> {
+    let key = request_api_key(req, auth.header());
+
+    auth.authenticate(key.as_ref().map(String::as_str)).map_err(|e| auth_error_response(res, e))
+}
+
+/// Same as `authenticate`, for the `/admin` endpoints: writes a 403 response for keys without the `admin` flag.
+pub fn authenticate_admin(auth: &ApiKeyAuthenticator, req: &SyncRequest, res: &mut SyncResponse) -> Result<ApiKey, ()> {
+    let key = request_api_key(req, auth.header());
+
+    auth.authenticate_admin(key.as_ref().map(String::as_str)).map_err(|e| auth_error_response(res, e))
+}
+
+fn auth_error_response(res: &mut SyncResponse, e: AuthError) {
+    match e {
+        AuthError::MissingKey | AuthError::InvalidKey => {
+            error_response(res, StatusCode::UNAUTHORIZED, "unauthorized", "A valid API key is required.");
+        }
+        AuthError::Forbidden => {
+            error_response(res, StatusCode::FORBIDDEN, "forbidden", "This API key cannot access the admin endpoints.");
+        }
+        AuthError::QuotaExceeded { retry_after_secs } => {
+            res.header("Retry-After", retry_after_secs.to_string());
+            error_response(res, StatusCode::TOO_MANY_REQUESTS, "quota_exceeded", "The daily quota of this API key is exhausted.");
+        }
+        AuthError::Repository(e) => {
+            error!("API key validation failed: {:?}", e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The API key could not be validated.");
+        }
+    }
+}
+
+/// Address of the client: the socket peer, unless the peer is a trusted proxy. Behind trusted proxies,
+/// the right-most `X-Forwarded-For` hop that is not itself a trusted proxy, or `X-Real-IP` without that header.
+pub fn client_address(limiter: &RateLimiter, req: &SyncRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+
+    if !limiter.is_trusted_proxy(&peer) {
+        return Some(peer);
+    }
+
+    let headers = req.headers_map();
+
+    if let Some(forwarded_for) = headers.get("X-Forwarded-For").and_then(|value| value.to_str().ok()) {
+        let mut client = peer;
+
+        // Hops are appended by each proxy, so only the ones after the last untrusted hop can be believed
+        for hop in forwarded_for.rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(addr) => client = addr,
+                Err(_) => break,
+            }
+
+            if !limiter.is_trusted_proxy(&client) {
+                break;
+            }
+        }
+
+        return Some(client);
+    }
+
+    headers.get("X-Real-IP")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<IpAddr>().ok())
+        .or(Some(peer))
+}
+
+/// Writes the 429 response and returns `Err` when `bucket` is exhausted. Requests are first limited per client
+/// address with `ip:<addr>` buckets, then per API key with `key:<key>` buckets once the key is authenticated.
+/// Limits the request by client address, to be checked before authenticating so that sending
+/// made-up keys does not get fresh buckets.
+pub fn rate_limit_client(limiter: &RateLimiter, req: &SyncRequest, res: &mut SyncResponse) -> Result<(), ()> {
+    let client = client_address(limiter, req).map_or_else(|| "unknown".to_string(), |addr| addr.to_string());
+    rate_limit(limiter, &format!("ip:{}", client), res)
+}
+
+pub fn rate_limit(limiter: &RateLimiter, bucket: &str, res: &mut SyncResponse) -> Result<(), ()> {
+    limiter.check(bucket).map_err(|retry_after_secs| {
+        res.header("Retry-After", retry_after_secs.to_string());
+        error_response(res, StatusCode::TOO_MANY_REQUESTS, "rate_limited", "Too many requests, slow down.");
+    })
+}
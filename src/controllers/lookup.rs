@@ -1,32 +1,77 @@
 use saphir::*;
 use saphir::Method;
-use serde_urlencoded::from_str;
-use std::net::SocketAddr;
+use chrono::Utc;
+use std::net::IpAddr;
 use std::str::FromStr;
-use crate::models::RepositoryCollection;
-use crate::models::Repository;
-use crate::models::ip::Ip;
-use mongodb::coll::options::FindOptions;
+use ipnet::IpNet;
+use crate::auth::ApiKeyAuthenticator;
+use crate::geo::{haversine_km, nearest_network};
+use crate::lookup::{find_location, Locator, LookupError, LookupResult};
+use crate::models::{Repository, RepositoryCollection, RepositoryError};
+use crate::networks::{networks_for_location, networks_in_range, LocationSelector, RangeError};
+use crate::rate_limit::RateLimiter;
+use crate::resolver::ResolveError;
+use crate::time_zone::time_zone_info;
+use super::helpers::{query_param, query_params, flag_param, page_params, coordinate_params, json_response, error_response, authenticate, rate_limit_client, rate_limit, add_routes, Route};
+use super::openapi::{document, openapi_json, OPENAPI_PATH};
+use super::schema::{DatasetResponse, DistanceResponse, DistanceTarget, HostAddress, HostAddressError, HostLookupResponse, LookupCoordinates, LookupLocation, LookupResponse, NearestResponse, NetworkEntry, NetworksResponse, RangeEntry, RangeResponse, RangeSummary, ReservedResponse};
 
 pub struct LookupController {
-    dispatch: ControllerDispatch<RepositoryCollection>,
+    dispatch: ControllerDispatch<Locator>,
+    auth: ApiKeyAuthenticator,
+    limiter: RateLimiter,
+    /// Serialized OpenAPI document
+    openapi: String,
 }
 
 impl LookupController {
-    pub fn new(repos: RepositoryCollection) -> Self {
-        let dispatch = ControllerDispatch::new(repos);
-        dispatch.add(Method::GET,
-                     reg!(r"^ip-lookup$"),
-                     ip_lookup);
+    /// Every route of the controller but `OPENAPI_PATH`, the OpenAPI document is tested against this table.
+    pub const ROUTES: &'static [Route<Locator>] = &[
+        Route { method: Method::GET, path: "/ip-lookup", handler: ip_lookup_v1 },
+        Route { method: Method::GET, path: "/v1/ip-lookup", handler: ip_lookup_v1 },
+        Route { method: Method::GET, path: "/v2/ip-lookup", handler: ip_lookup_v2 },
+        Route { method: Method::GET, path: "/stats", handler: stats },
+        Route { method: Method::GET, path: "/dataset", handler: dataset },
+        Route { method: Method::GET, path: "/v2/networks", handler: networks },
+        Route { method: Method::GET, path: "/geo/nearest", handler: geo_nearest },
+        Route { method: Method::GET, path: "/distance", handler: distance },
+        Route { method: Method::GET, path: "/range", handler: range },
+    ];
+
+    pub fn new(locator: Locator, auth: ApiKeyAuthenticator, limiter: RateLimiter) -> Self {
+        let dispatch = ControllerDispatch::new(locator);
+        add_routes(&dispatch, "/", Self::ROUTES);
+        let openapi = serde_json::to_string(&document(auth.header())).expect("Will be ok");
 
         LookupController {
-            dispatch
+            dispatch,
+            auth,
+            limiter,
+            openapi,
         }
     }
 }
 
 impl Controller for LookupController {
     fn handle(&self, req: &mut SyncRequest, res: &mut SyncResponse) {
+        if rate_limit_client(&self.limiter, req, res).is_err() {
+            return;
+        }
+
+        if *req.method() == Method::GET && req.uri().path() == OPENAPI_PATH {
+            return openapi_json(&self.openapi, res);
+        }
+
+        match authenticate(&self.auth, req, res) {
+            Ok(Some(api_key)) => {
+                if rate_limit(&self.limiter, &format!("key:{}", api_key.key), res).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(()) => return,
+        }
+
         self.dispatch.dispatch(req, res);
     }
 
@@ -61,122 +106,367 @@ fn parse(repos: &RepositoryCollection, req: &SyncRequest, res: &mut SyncResponse
     }*/
 }
 
-fn ip_lookup(repos: &RepositoryCollection, req: &SyncRequest, res: &mut SyncResponse) {
+/// Legacy response shape, served at `/ip-lookup` and `/v1/ip-lookup`. Do not change it.
+fn ip_lookup_v1(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
     res.status(StatusCode::BAD_REQUEST);
 
     let error_json = json!({
         "error": "The request IP was not found in the database."
     });
 
-    if let Some(query) = req.uri().query() {
-        if let Ok(params) = serde_urlencoded::from_str::<Vec<(String, String)>>(query) {
-            if let Some(ip) = params.get(0) {
-                if ip.0 == "ip" {
-                    match SocketAddr::from_str(&format!("{}:80", &ip.1)) {
-                        Ok(addr) => {
-                            let ip_str = addr.ip().to_string();
-                            let ip_parts = ip_str.split(".").collect::<Vec<&str>>();
-                            let net: i32 = ip_parts[0].parse::<i32>().expect("Ok");
-                            let sub: i32 = ip_parts[1].parse::<i32>().expect("Ok");
-                            let sub2: i32 = ip_parts[2].parse::<i32>().expect("Ok");
-                            let mask: i32 = ip_parts[3].parse::<i32>().expect("Ok");
-
-                            let filtered_ips = match repos.ip.count(Some(doc! {"net":net, "sub":sub})) {
-                                Ok(count) if count > 0 => {
-                                    let ips_sub2 = match repos.ip.count(Some(doc! {"net":net, "sub":sub, "sub2": sub2})) {
-                                        Ok(count) if count > 0 => {
-                                            repos.ip.find(doc! {"net":net, "sub":sub, "sub2": sub2}).unwrap()
-                                        }
-                                        _ => {
-                                            let mut options = FindOptions::new();
-                                            options.limit = Some(sub2 as i64);
-                                            options.sort = Some(doc!{"sub": -1});
-                                            let mut lte_ips_sub = repos.ip.find_with_options(doc! {"net":net, "sub": sub, "sub2": { "$lte": sub2 }}, options).unwrap();
-
-                                            if let Some(ref ip) = lte_ips_sub.first() {
-                                                let l_sub = ip.sub2;
-                                                while let Some(_) = lte_ips_sub.iter().position(|i| i.sub2 != l_sub).map(|idx| lte_ips_sub.remove(idx)) {}
-                                            }
-
-                                            lte_ips_sub
-                                        }
-                                    };
-
-                                    ips_sub2
-                                }
-                                _ => {
-                                    let mut options = FindOptions::new();
-                                    options.limit = Some(sub2 as i64);
-                                    options.sort = Some(doc!{"sub": -1});
-                                    let mut lte_ips_sub = repos.ip.find_with_options(doc! {"net":net, "sub": { "$lte": sub }, "sub2": { "$lte": sub2 }}, options).unwrap();
-
-                                    if let Some(ref ip) = lte_ips_sub.first() {
-                                        let l_sub = ip.sub;
-                                        while let Some(_) = lte_ips_sub.iter().position(|i| i.sub != l_sub).map(|idx| lte_ips_sub.remove(idx)) {}
-                                    }
-
-                                    if lte_ips_sub.len() < 1 {
-                                        res.status(StatusCode::OK);
-                                        res.body(serde_json::to_string(&error_json).expect("Will be ok"));
-                                    }
-
-                                    lte_ips_sub
-                                }
-                            };
-
-                            if filtered_ips.len() > 0{
-                                let the_right_one = find_right_entry(&filtered_ips);
-
-                                if let Ok(Some(location)) = repos.location.get(doc! {"geoname_id": the_right_one.geoname_id}) {
-                                    let json = json!({
-                                    "request_ip": ip_str,
-                                    "network": the_right_one.network,
-                                    "lat": the_right_one.latitude,
-                                    "lon": the_right_one.longitude,
-                                    "accuracy": the_right_one.accuracy_radius,
-                                    "continent": location.continent_name,
-                                    "country": location.country_name,
-                                    "subdivision_1_name": location.subdivision_1_name,
-                                    "subdivision_2_name": location.subdivision_2_name,
-                                    "city_name": location.city_name,
-                                    "time_zone": location.time_zone,
-                                });
-
-                                    res.status(StatusCode::OK);
-                                    res.body(serde_json::to_string(&json).expect("Will be ok"));
-                                }
-                            }
-                            else{
-                                res.status(StatusCode::OK);
-                                res.body(serde_json::to_string(&error_json).expect("Will be ok"));
-                            }
-                        }
-                        _ => {}
-                    }
-                }
+    // v1 only ever honored `ip` when it was the first query parameter
+    let addr = match query_params(req).into_iter().next() {
+        Some((key, value)) if key == "ip" => match IpAddr::from_str(&value) {
+            Ok(addr) => addr,
+            Err(_) => return,
+        },
+        _ => return,
+    };
+
+    match locator.lookup(addr) {
+        Ok(result) => {
+            res.status(StatusCode::OK);
+            res.body(serde_json::to_string(&v1_json(&result)).expect("Will be ok"));
+        }
+        Err(LookupError::NetworkNotFound) | Err(LookupError::Reserved(_)) => {
+            res.status(StatusCode::OK);
+            res.body(serde_json::to_string(&error_json).expect("Will be ok"));
+        }
+        Err(LookupError::Repository(e)) => {
+            error!("Lookup of {} failed: {:?}", addr, e);
+            res.status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Err(_) => {}
+    }
+}
+
+fn v1_json(result: &LookupResult) -> serde_json::Value {
+    json!({
+        "request_ip": result.request_ip.to_string(),
+        "network": result.ip.network,
+        "lat": result.ip.latitude,
+        "lon": result.ip.longitude,
+        "accuracy": result.ip.accuracy_radius,
+        "continent": result.location.continent_name,
+        "country": result.location.country_name,
+        "subdivision_1_name": result.location.subdivision_1_name,
+        "subdivision_2_name": result.location.subdivision_2_name,
+        "city_name": result.location.city_name,
+        "time_zone": result.location.time_zone,
+    })
+}
+
+fn ip_lookup_v2(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    if query_param(req, "ip").is_none() {
+        return match query_param(req, "host") {
+            Some(host) => host_lookup(locator, req, res, &host),
+            None => error_response(res, StatusCode::BAD_REQUEST, "missing_ip", "One of the `ip` or `host` query parameters is required."),
+        };
+    }
+
+    let addr = match ip_param(req, res, "ip") {
+        Some(addr) => addr,
+        None => return,
+    };
+
+    match locator.lookup(addr) {
+        Ok(result) => json_response(res, StatusCode::OK, &v2_response(locator, req, &result)),
+        Err(LookupError::Reserved(range)) => json_response(res, StatusCode::OK, &ReservedResponse::new(addr, range)),
+        Err(e) => lookup_error_response(res, addr, e),
+    }
+}
+
+fn v2_response(locator: &Locator, req: &SyncRequest, result: &LookupResult) -> LookupResponse {
+    let mut lookup_response = LookupResponse::from(result);
+
+    if flag_param(req, "tz") {
+        lookup_response.time_zone_details = time_zone_info(&result.location.time_zone, Utc::now());
+    }
+
+    if flag_param(req, "rdns") {
+        lookup_response.hostname = locator.hostname(result.request_ip);
+    }
+
+    lookup_response
+}
+
+/// v2 lookup of `host=`, answering for every address the host resolves to.
+fn host_lookup(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse, host: &str) {
+    let results = match locator.lookup_host(host) {
+        Ok(results) => results,
+        Err(ResolveError::InvalidHost) => return error_response(res, StatusCode::BAD_REQUEST, "invalid_host", "The `host` parameter is not a valid hostname."),
+        Err(ResolveError::NotFound(reason)) => {
+            debug!("Unable to resolve {}: {}", host, reason);
+            return error_response(res, StatusCode::NOT_FOUND, "host_not_found", &format!("{} could not be resolved.", host));
+        }
+    };
+
+    let mut addresses = Vec::new();
+
+    for (addr, result) in results {
+        addresses.push(match result {
+            Ok(result) => HostAddress::Located(v2_response(locator, req, &result)),
+            Err(LookupError::Reserved(range)) => HostAddress::Reserved(ReservedResponse::new(addr, range)),
+            Err(LookupError::Repository(e)) => {
+                error!("Lookup of {} ({}) failed: {:?}", addr, host, e);
+                return error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The lookup could not be completed.");
+            }
+            Err(e) => {
+                let (_, code, message) = lookup_error(addr, &e);
+                HostAddress::Failed(HostAddressError::new(addr, code, message))
             }
+        });
+    }
+
+    let host_response = HostLookupResponse {
+        host: host.to_string(),
+        addresses,
+    };
+
+    json_response(res, StatusCode::OK, &host_response)
+}
+
+/// Reads an IP address from the query, writing the 400 response when it is missing or invalid.
+fn ip_param(req: &SyncRequest, res: &mut SyncResponse, name: &str) -> Option<IpAddr> {
+    match query_param(req, name).map(|ip| IpAddr::from_str(&ip)) {
+        Some(Ok(addr)) => Some(addr),
+        Some(Err(_)) => {
+            error_response(res, StatusCode::BAD_REQUEST, "invalid_ip", &format!("The `{}` parameter is not a valid IP address.", name));
+            None
+        }
+        None => {
+            error_response(res, StatusCode::BAD_REQUEST, "missing_ip", &format!("The `{}` query parameter is required.", name));
+            None
         }
     }
 }
 
-fn find_right_entry(ips: &Vec<Ip>) -> Ip {
-    let mut vec_value = get_netmask_array(ips);
-    vec_value.sort_by(|a, b| a.0.cmp(&b.0));
-    vec_value.last().unwrap().1.clone()
+fn lookup_error_response(res: &mut SyncResponse, addr: IpAddr, e: LookupError) {
+    if let LookupError::Repository(ref e) = e {
+        error!("Lookup of {} failed: {:?}", addr, e);
+    }
+
+    let (status, code, message) = lookup_error(addr, &e);
+    error_response(res, status, code, &message)
 }
 
-fn get_netmask_array(ips: &Vec<Ip>) -> Vec<(i64, &Ip)> {
-    let mut vec_ips = Vec::new();
-    for ip in ips {
-        let val = get_netmask_value(ip.netmask.as_str());
-        vec_ips.push((val, ip));
+/// Status, code and message describing a failed lookup of `addr`.
+fn lookup_error(addr: IpAddr, e: &LookupError) -> (StatusCode, &'static str, String) {
+    match e {
+        LookupError::Reserved(range) => (StatusCode::UNPROCESSABLE_ENTITY, "reserved_address", format!("{} belongs to {} ({}) and has no location.", addr, range.network, range.purpose)),
+        LookupError::UnsupportedAddress => (StatusCode::UNPROCESSABLE_ENTITY, "unsupported_address", format!("{} cannot be looked up, only IPv4 addresses are supported.", addr)),
+        LookupError::NetworkNotFound | LookupError::LocationNotFound => (StatusCode::NOT_FOUND, "not_found", format!("{} was not found in the database.", addr)),
+        LookupError::Repository(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The lookup could not be completed.".to_string()),
     }
+}
 
-    vec_ips
+fn stats(locator: &Locator, _req: &SyncRequest, res: &mut SyncResponse) {
+    let stats_json = json!({
+        "cache": locator.cache_stats(),
+        "reverse_dns_cache": locator.reverse_dns_stats(),
+    });
+
+    json_response(res, StatusCode::OK, &stats_json);
 }
 
-fn get_netmask_value(netmask: &str) -> i64 {
-    let cyrille = netmask.split('/').collect::<Vec<_>>();
+fn dataset(locator: &Locator, _req: &SyncRequest, res: &mut SyncResponse) {
+    match dataset_response(&locator.repos) {
+        Ok(dataset) => json_response(res, StatusCode::OK, &dataset),
+        Err(e) => {
+            error!("Unable to describe the active dataset: {:?}", e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The dataset metadata could not be read.")
+        }
+    }
+}
+
+fn dataset_response(repos: &RepositoryCollection) -> Result<DatasetResponse, RepositoryError> {
+    let version = repos.dataset_version();
+    let activated_at = repos.active_dataset()?.map(|active| active.switched_at);
+
+    let info = match version {
+        Some(ref version) => repos.dataset_info(version)?,
+        None => None,
+    };
+
+    Ok(match info {
+        Some(info) => DatasetResponse {
+            version,
+            source: Some(info.source),
+            build_date: Some(info.build_date),
+            ip_count: info.ip_count,
+            location_count: info.location_count,
+            imported_at: Some(info.imported_at),
+            activated_at,
+        },
+        None => DatasetResponse {
+            version,
+            source: None,
+            build_date: None,
+            ip_count: repos.ip.count(None)?,
+            location_count: repos.location.count(None)?,
+            imported_at: None,
+            activated_at,
+        },
+    })
+}
+
+fn networks(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let selector = if let Some(geoname_id) = query_param(req, "geoname_id") {
+        LocationSelector::GeonameId(geoname_id)
+    } else if let Some(country) = query_param(req, "country") {
+        LocationSelector::Country(country)
+    } else if let Some(city) = query_param(req, "city") {
+        LocationSelector::City(city)
+    } else {
+        return error_response(res, StatusCode::BAD_REQUEST, "missing_location", "One of `geoname_id`, `country` or `city` is required.");
+    };
+
+    let (page, per_page) = match page_params(req, 100, 1000) {
+        Ok(params) => params,
+        Err(message) => return error_response(res, StatusCode::BAD_REQUEST, "invalid_page", &message),
+    };
+
+    match networks_for_location(&locator.repos, &selector, page - 1, per_page) {
+        Ok(result) => {
+            let networks_response = NetworksResponse {
+                geoname_ids: result.geoname_ids,
+                total: result.total,
+                page,
+                per_page,
+                networks: result.networks.iter().map(NetworkEntry::from).collect(),
+            };
+
+            json_response(res, StatusCode::OK, &networks_response)
+        }
+        Err(e) => {
+            error!("Reverse lookup of {:?} failed: {:?}", selector, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The networks could not be listed.")
+        }
+    }
+}
+
+fn geo_nearest(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let (latitude, longitude) = match coordinate_params(req, "lat", "lon") {
+        Ok(coordinates) => coordinates,
+        Err(message) => return error_response(res, StatusCode::BAD_REQUEST, "invalid_coordinates", &message),
+    };
+
+    let nearest = nearest_network(&locator.repos, latitude, longitude).and_then(|nearest| match nearest {
+        Some((ip, distance_km)) => Ok(find_location(&locator.repos, &ip)?.map(|location| (ip, distance_km, location))),
+        None => Ok(None),
+    });
+
+    match nearest {
+        Ok(Some((ip, distance_km, (location, location_source)))) => {
+            let nearest_response = NearestResponse {
+                latitude,
+                longitude,
+                distance_km,
+                network: ip.network.clone(),
+                coordinates: LookupCoordinates::from(&ip),
+                location: LookupLocation::from(&location),
+                location_source,
+            };
+
+            json_response(res, StatusCode::OK, &nearest_response)
+        }
+        Ok(None) => error_response(res, StatusCode::NOT_FOUND, "not_found", "No located network was found, the dataset may have been imported without centroids."),
+        Err(e) => {
+            error!("Nearest location search at {},{} failed: {:?}", latitude, longitude, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The nearest location could not be searched.")
+        }
+    }
+}
 
-    (cyrille[0].parse::<i64>().unwrap() * 8) + (cyrille[1].parse::<i64>().unwrap() * 256)
-}
\ No newline at end of file
+/// Distance from the `from` IP to either the `to` IP or the `to_lat`/`to_lon` point.
+fn distance(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let from_addr = match ip_param(req, res, "from") {
+        Some(addr) => addr,
+        None => return,
+    };
+
+    let from = match locator.lookup(from_addr) {
+        Ok(result) => LookupResponse::from(&result),
+        Err(e) => return lookup_error_response(res, from_addr, e),
+    };
+
+    let to = if query_param(req, "to").is_some() {
+        let to_addr = match ip_param(req, res, "to") {
+            Some(addr) => addr,
+            None => return,
+        };
+
+        match locator.lookup(to_addr) {
+            Ok(result) => DistanceTarget::Ip(LookupResponse::from(&result)),
+            Err(e) => return lookup_error_response(res, to_addr, e),
+        }
+    } else {
+        match coordinate_params(req, "to_lat", "to_lon") {
+            Ok((latitude, longitude)) => DistanceTarget::Point { latitude, longitude },
+            Err(message) => return error_response(res, StatusCode::BAD_REQUEST, "invalid_target", &format!("Either `to` or `to_lat` and `to_lon` are required: {}", message)),
+        }
+    };
+
+    let (to_latitude, to_longitude, to_accuracy) = match to {
+        DistanceTarget::Ip(ref to) => (to.coordinates.latitude, to.coordinates.longitude, to.coordinates.accuracy_radius_km),
+        DistanceTarget::Point { latitude, longitude } => (Some(latitude), Some(longitude), Some(0)),
+    };
+
+    let distance_km = match (from.coordinates.latitude, from.coordinates.longitude, to_latitude, to_longitude) {
+        (Some(from_latitude), Some(from_longitude), Some(to_latitude), Some(to_longitude)) => haversine_km(from_latitude, from_longitude, to_latitude, to_longitude),
+        _ => return error_response(res, StatusCode::UNPROCESSABLE_ENTITY, "missing_coordinates", "One of the IPs has no coordinates in the database."),
+    };
+
+    let distance_response = DistanceResponse {
+        distance_km,
+        accuracy_radius_km: from.coordinates.accuracy_radius_km.and_then(|from_accuracy| to_accuracy.map(|to_accuracy| from_accuracy + to_accuracy)),
+        from,
+        to,
+    };
+
+    json_response(res, StatusCode::OK, &distance_response)
+}
+
+/// Largest block `/range` accepts, bigger ones would scan most of the collection
+const MIN_RANGE_PREFIX_LENGTH: u8 = 8;
+
+fn range(locator: &Locator, req: &SyncRequest, res: &mut SyncResponse) {
+    let range = match query_param(req, "cidr").map(|cidr| cidr.parse::<IpNet>()) {
+        Some(Ok(IpNet::V4(range))) => range.trunc(),
+        Some(Ok(IpNet::V6(_))) => return error_response(res, StatusCode::UNPROCESSABLE_ENTITY, "unsupported_address", "Only IPv4 blocks are supported."),
+        Some(Err(_)) => return error_response(res, StatusCode::BAD_REQUEST, "invalid_cidr", "The `cidr` parameter is not a valid CIDR."),
+        None => return error_response(res, StatusCode::BAD_REQUEST, "missing_cidr", "The `cidr` query parameter is required."),
+    };
+
+    if range.prefix_len() < MIN_RANGE_PREFIX_LENGTH {
+        return error_response(res, StatusCode::UNPROCESSABLE_ENTITY, "range_too_large", &format!("The block must be a /{} or smaller.", MIN_RANGE_PREFIX_LENGTH));
+    }
+
+    let (page, per_page) = match page_params(req, 100, 1000) {
+        Ok(params) => params,
+        Err(message) => return error_response(res, StatusCode::BAD_REQUEST, "invalid_page", &message),
+    };
+
+    match networks_in_range(&locator.repos, &range, page - 1, per_page) {
+        Ok(result) => {
+            let range_response = RangeResponse {
+                cidr: range.to_string(),
+                total: result.total,
+                page,
+                per_page,
+                summary: RangeSummary::from(result.locations.as_slice()),
+                networks: result.networks.iter().map(RangeEntry::from).collect(),
+            };
+
+            json_response(res, StatusCode::OK, &range_response)
+        }
+        Err(RangeError::MissingRangeFields(count)) => {
+            warn!("Range query of {} refused, {} networks have no range fields", range, count);
+            error_response(res, StatusCode::SERVICE_UNAVAILABLE, "range_fields_missing", "The active dataset was imported without range fields, import it again to enable this endpoint.")
+        }
+        Err(RangeError::Repository(e)) => {
+            error!("Range query of {} failed: {:?}", range, e);
+            error_response(res, StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "The networks could not be listed.")
+        }
+    }
+}
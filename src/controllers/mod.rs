@@ -1,3 +1,8 @@
+mod admin;
+mod helpers;
 mod lookup;
+pub mod openapi;
+pub mod schema;
 
+pub use self::admin::AdminController;
 pub use self::lookup::LookupController;
\ No newline at end of file
@@ -0,0 +1,841 @@
+use saphir::*;
+use serde_json::{Map, Value};
+
+/// Served without authentication so clients can discover how to authenticate.
+pub const OPENAPI_PATH: &'static str = "/openapi.json";
+
+/// `document` is built once by the controller, from the configured API key header.
+pub fn openapi_json(document: &str, res: &mut SyncResponse) {
+    res.status(StatusCode::OK);
+    res.header("Content-Type", "application/json");
+    res.body(document.to_string());
+}
+
+fn ip_parameter() -> Value {
+    json!({
+        "name": "ip",
+        "in": "query",
+        "required": true,
+        "description": "IPv4 address to geolocalize",
+        "schema": { "type": "string", "format": "ipv4" }
+    })
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": "#/components/schemas/Error" }
+            }
+        }
+    })
+}
+
+fn json_content(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+            }
+        }
+    })
+}
+
+/// Collection and item paths of an `/admin` resource. Admin keys are required even when authentication is disabled.
+/// `write_errors` are the validation responses of the create and replace operations.
+fn admin_paths(paths: &mut Map<String, Value>, path: &str, record: &str, schema: &str, list_schema: &str, filters: Vec<Value>, write_errors: &[(&str, &str)]) {
+    let security = json!([{ "ApiKeyHeader": [] }, { "ApiKeyQuery": [] }]);
+    let mut list_parameters = vec![
+        json!({ "name": "page", "in": "query", "schema": { "type": "integer", "minimum": 1, "default": 1 } }),
+        json!({ "name": "per_page", "in": "query", "schema": { "type": "integer", "minimum": 1, "maximum": 1000, "default": 100 } }),
+    ];
+    list_parameters.extend(filters);
+    let id_parameter = json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string", "pattern": "^[0-9a-fA-F]{24}$" } });
+    let request_body = json!({
+        "required": true,
+        "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } }
+    });
+
+    let list = json!({
+        "summary": format!("List the {}s", record),
+        "security": security,
+        "parameters": list_parameters,
+        "responses": {
+            "200": json_content(&format!("A page of {}s", record), list_schema),
+            "400": error_response("Invalid pagination parameters"),
+            "401": error_response("Missing or invalid API key"),
+            "403": error_response("The API key is not an admin key"),
+            "429": error_response("Rate limited, see `Retry-After`"),
+            "500": error_response("The database could not be queried")
+        }
+    });
+    let mut create = json!({
+        "summary": format!("Create a {}", record),
+        "security": security,
+        "requestBody": request_body,
+        "responses": {
+            "201": json_content(&format!("The created {}", record), schema),
+            "400": error_response("The body is not valid JSON"),
+            "401": error_response("Missing or invalid API key"),
+            "403": error_response("The API key is not an admin key"),
+            "429": error_response("Rate limited, see `Retry-After`"),
+            "500": error_response("The database could not be queried")
+        }
+    });
+    let read = json!({
+        "summary": format!("Read a {}", record),
+        "security": security,
+        "parameters": [id_parameter],
+        "responses": {
+            "200": json_content(&format!("The {}", record), schema),
+            "401": error_response("Missing or invalid API key"),
+            "403": error_response("The API key is not an admin key"),
+            "429": error_response("Rate limited, see `Retry-After`"),
+            "404": error_response(&format!("No {} has this id", record)),
+            "500": error_response("The database could not be queried")
+        }
+    });
+    let mut update = json!({
+        "summary": format!("Replace a {}", record),
+        "security": security,
+        "parameters": [id_parameter],
+        "requestBody": request_body,
+        "responses": {
+            "200": json_content(&format!("The updated {}", record), schema),
+            "400": error_response("The body is not valid JSON"),
+            "401": error_response("Missing or invalid API key"),
+            "403": error_response("The API key is not an admin key"),
+            "429": error_response("Rate limited, see `Retry-After`"),
+            "404": error_response(&format!("No {} has this id", record)),
+            "500": error_response("The database could not be queried")
+        }
+    });
+    let delete = json!({
+        "summary": format!("Delete a {}", record),
+        "security": security,
+        "parameters": [id_parameter],
+        "responses": {
+            "204": { "description": format!("The {} was deleted", record) },
+            "401": error_response("Missing or invalid API key"),
+            "403": error_response("The API key is not an admin key"),
+            "429": error_response("Rate limited, see `Retry-After`"),
+            "404": error_response(&format!("No {} has this id", record)),
+            "500": error_response("The database could not be queried")
+        }
+    });
+
+    for operation in vec![&mut create, &mut update] {
+        for (status, description) in write_errors {
+            operation["responses"][*status] = error_response(description);
+        }
+    }
+
+    paths.insert(path.to_string(), json!({ "get": list, "post": create }));
+    paths.insert(format!("{}/{{id}}", path), json!({ "get": read, "put": update, "delete": delete }));
+}
+
+fn v1_operation(deprecated: bool) -> Value {
+    json!({
+        "summary": "Geolocalize an IP (legacy response shape)",
+        "deprecated": deprecated,
+        "parameters": [ip_parameter()],
+        "responses": {
+            "200": {
+                "description": "The matching network and location, or a `V1NotFound` body when the IP is unknown",
+                "content": {
+                    "application/json": {
+                        "schema": {
+                            "oneOf": [
+                                { "$ref": "#/components/schemas/V1LookupResponse" },
+                                { "$ref": "#/components/schemas/V1NotFound" }
+                            ]
+                        }
+                    }
+                }
+            },
+            "400": { "description": "Missing or invalid `ip` parameter, or no location for the matching network (empty body)" },
+            "401": error_response("Missing or invalid API key, when authentication is enabled"),
+            "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`"),
+            "500": { "description": "The database could not be queried (empty body)" }
+        }
+    })
+}
+
+pub fn document(api_key_header: &str) -> Value {
+    json!({
+        "openapi": "3.0.2",
+        "info": {
+            "title": "spotme",
+            "description": "API to geolocalize an IP",
+            "version": crate_version!()
+        },
+        "security": [{}, { "ApiKeyHeader": [] }, { "ApiKeyQuery": [] }],
+        "paths": paths(),
+        "components": {
+            "securitySchemes": {
+                "ApiKeyHeader": { "type": "apiKey", "in": "header", "name": api_key_header },
+                "ApiKeyQuery": { "type": "apiKey", "in": "query", "name": "api_key" }
+            },
+            "schemas": schemas()
+        }
+    })
+}
+
+fn paths() -> Value {
+    let mut paths = Map::new();
+
+    paths.insert("/ip-lookup".to_string(), json!({
+        "get": v1_operation(true)
+    }));
+    paths.insert("/v1/ip-lookup".to_string(), json!({
+        "get": v1_operation(false)
+    }));
+    paths.insert("/v2/ip-lookup".to_string(), json!({
+        "get": {
+            "summary": "Geolocalize an IP, or every address of a hostname",
+            "parameters": [
+                { "name": "ip", "in": "query", "description": "Address to geolocalize, required unless `host` is given", "schema": { "type": "string" } },
+                { "name": "host", "in": "query", "description": "Hostname whose A and AAAA addresses are geolocalized, ignored when `ip` is given", "schema": { "type": "string", "format": "hostname" } },
+                { "name": "tz", "in": "query", "description": "Include the current offset of the time zone", "schema": { "type": "boolean", "default": false } },
+                { "name": "rdns", "in": "query", "description": "Include the PTR name of the address, omitted when the resolver does not answer within `resolver.rdns_timeout_ms`", "schema": { "type": "boolean", "default": false } }
+            ],
+            "responses": {
+                "200": {
+                    "description": "The matching network and location, or the special-purpose block of a reserved address. A `HostLookupResponse` for `host` lookups",
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "oneOf": [
+                                    { "$ref": "#/components/schemas/LookupResponse" },
+                                    { "$ref": "#/components/schemas/ReservedResponse" },
+                                    { "$ref": "#/components/schemas/HostLookupResponse" }
+                                ]
+                            }
+                        }
+                    }
+                },
+                "400": error_response("Missing or invalid `ip` or `host` parameter"),
+                "401": error_response("Missing or invalid API key, when authentication is enabled"),
+                "404": error_response("The IP is not in the database, or the host could not be resolved"),
+                "422": error_response("The IP family is not supported"),
+                "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`"),
+                "500": error_response("The database could not be queried")
+            }
+        }
+    }));
+    paths.insert("/v2/networks".to_string(), json!({
+        "get": {
+            "summary": "List the networks mapped to a location",
+            "description": "Exactly one of `geoname_id`, `country` or `city` is used, in that order of precedence",
+            "parameters": [
+                { "name": "geoname_id", "in": "query", "schema": { "type": "string" } },
+                { "name": "country", "in": "query", "description": "ISO 3166-1 alpha-2 code", "schema": { "type": "string" } },
+                { "name": "city", "in": "query", "description": "Exact city name", "schema": { "type": "string" } },
+                { "name": "page", "in": "query", "schema": { "type": "integer", "minimum": 1, "default": 1 } },
+                { "name": "per_page", "in": "query", "schema": { "type": "integer", "minimum": 1, "maximum": 1000, "default": 100 } }
+            ],
+            "responses": {
+                "200": {
+                    "description": "One page of networks, ordered by address",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/NetworksResponse" }
+                        }
+                    }
+                },
+                "400": error_response("No location given or invalid pagination"),
+                "401": error_response("Missing or invalid API key, when authentication is enabled"),
+                "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`"),
+                "500": error_response("The database could not be queried")
+            }
+        }
+    }));
+    paths.insert("/geo/nearest".to_string(), json!({
+        "get": {
+            "summary": "Find the location closest to a point",
+            "description": "Searches the centroids of the networks, so the answer is one of the locations IP lookups return",
+            "parameters": [
+                { "name": "lat", "in": "query", "required": true, "schema": { "type": "number", "minimum": -90, "maximum": 90 } },
+                { "name": "lon", "in": "query", "required": true, "schema": { "type": "number", "minimum": -180, "maximum": 180 } }
+            ],
+            "responses": {
+                "200": {
+                    "description": "The closest located network and its location",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/NearestResponse" }
+                        }
+                    }
+                },
+                "400": error_response("Missing or out of range coordinates"),
+                "401": error_response("Missing or invalid API key, when authentication is enabled"),
+                "404": error_response("No network of the dataset has a centroid"),
+                "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`"),
+                "500": error_response("The database could not be queried")
+            }
+        }
+    }));
+    paths.insert("/distance".to_string(), json!({
+        "get": {
+            "summary": "Great-circle distance between two IPs, or an IP and a point",
+            "parameters": [
+                { "name": "from", "in": "query", "required": true, "schema": { "type": "string", "format": "ipv4" } },
+                { "name": "to", "in": "query", "description": "Required unless `to_lat` and `to_lon` are given", "schema": { "type": "string", "format": "ipv4" } },
+                { "name": "to_lat", "in": "query", "schema": { "type": "number", "minimum": -90, "maximum": 90 } },
+                { "name": "to_lon", "in": "query", "schema": { "type": "number", "minimum": -180, "maximum": 180 } }
+            ],
+            "responses": {
+                "200": {
+                    "description": "Both ends and the distance between them",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/DistanceResponse" }
+                        }
+                    }
+                },
+                "400": error_response("Missing or invalid IP or coordinates"),
+                "401": error_response("Missing or invalid API key, when authentication is enabled"),
+                "404": error_response("One of the IPs is not in the database"),
+                "422": error_response("One of the IPs is reserved, not supported or has no coordinates"),
+                "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`"),
+                "500": error_response("The database could not be queried")
+            }
+        }
+    }));
+    paths.insert("/range".to_string(), json!({
+        "get": {
+            "summary": "List the networks overlapping an IPv4 block",
+            "description": "Host bits of `cidr` are ignored. The summary covers every overlapping network, not only the returned page",
+            "parameters": [
+                { "name": "cidr", "in": "query", "required": true, "description": "A /8 or smaller block", "schema": { "type": "string", "example": "203.0.113.0/22" } },
+                { "name": "page", "in": "query", "schema": { "type": "integer", "minimum": 1, "default": 1 } },
+                { "name": "per_page", "in": "query", "schema": { "type": "integer", "minimum": 1, "maximum": 1000, "default": 100 } }
+            ],
+            "responses": {
+                "200": {
+                    "description": "One page of networks, ordered by address, and the locations of the whole block",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/RangeResponse" }
+                        }
+                    }
+                },
+                "400": error_response("Missing or invalid CIDR or pagination"),
+                "401": error_response("Missing or invalid API key, when authentication is enabled"),
+                "422": error_response("IPv6 block, or block larger than a /8"),
+                "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`"),
+                "500": error_response("The database could not be queried"),
+                "503": error_response("The active dataset has networks without range fields, it must be imported again")
+            }
+        }
+    }));
+    paths.insert("/stats".to_string(), json!({
+        "get": {
+            "summary": "Runtime counters",
+            "responses": {
+                "200": {
+                    "description": "Lookup and reverse DNS cache counters",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/Stats" }
+                        }
+                    }
+                },
+                "401": error_response("Missing or invalid API key, when authentication is enabled"),
+                "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`")
+            }
+        }
+    }));
+    paths.insert("/dataset".to_string(), json!({
+        "get": {
+            "summary": "Describe the dataset currently served",
+            "responses": {
+                "200": {
+                    "description": "Metadata of the active dataset version",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/DatasetResponse" }
+                        }
+                    }
+                },
+                "401": error_response("Missing or invalid API key, when authentication is enabled"),
+                "429": error_response("Rate limited or daily quota of the API key exhausted, see `Retry-After`"),
+                "500": error_response("The database could not be queried")
+            }
+        }
+    }));
+    admin_paths(&mut paths, "/admin/overrides", "network override", "NetworkOverride", "NetworkOverrideList", vec![
+        json!({ "name": "label", "in": "query", "description": "Only the overrides having this label", "schema": { "type": "string" } })
+    ], &[("422", "Invalid network or coordinates")]);
+    admin_paths(&mut paths, "/admin/ip", "network", "AdminIp", "AdminIpList", vec![
+        json!({ "name": "network", "in": "query", "schema": { "type": "string" } }),
+        json!({ "name": "geoname_id", "in": "query", "schema": { "type": "string" } })
+    ], &[("422", "Invalid network, coordinates or accuracy radius")]);
+    admin_paths(&mut paths, "/admin/location", "location", "AdminLocation", "AdminLocationList", vec![
+        json!({ "name": "geoname_id", "in": "query", "schema": { "type": "string" } }),
+        json!({ "name": "country", "in": "query", "description": "ISO 3166-1 alpha-2 code", "schema": { "type": "string" } }),
+        json!({ "name": "city", "in": "query", "schema": { "type": "string" } })
+    ], &[("409", "Another location has this geoname_id"), ("422", "Missing geoname_id, invalid country code or time zone")]);
+    paths.insert(OPENAPI_PATH.to_string(), json!({
+        "get": {
+            "summary": "This document",
+            "security": [],
+            "responses": {
+                "200": {
+                    "description": "The OpenAPI 3 document describing this server",
+                    "content": { "application/json": { "schema": { "type": "object" } } }
+                },
+                "429": error_response("Rate limited, see `Retry-After`")
+            }
+        }
+    }));
+
+    Value::Object(paths)
+}
+
+fn schemas() -> Value {
+    let mut schemas = Map::new();
+
+    schemas.insert("V1LookupResponse".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "request_ip": { "type": "string" },
+            "network": { "type": "string" },
+            "lat": { "type": "string" },
+            "lon": { "type": "string" },
+            "accuracy": { "type": "string" },
+            "continent": { "type": "string" },
+            "country": { "type": "string" },
+            "subdivision_1_name": { "type": "string" },
+            "subdivision_2_name": { "type": "string" },
+            "city_name": { "type": "string" },
+            "time_zone": { "type": "string" }
+        }
+    }));
+    schemas.insert("V1NotFound".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "error": { "type": "string" }
+        }
+    }));
+    schemas.insert("LookupResponse".to_string(), json!({
+        "type": "object",
+        "required": ["ip", "network", "coordinates", "location", "location_source", "confidence", "is_tor", "is_anonymous_proxy", "is_hosting"],
+        "properties": {
+            "ip": { "type": "string" },
+            "network": { "type": "string", "description": "CIDR of the matching network" },
+            "coordinates": { "$ref": "#/components/schemas/LookupCoordinates" },
+            "location": { "$ref": "#/components/schemas/LookupLocation" },
+            "location_source": {
+                "type": "string",
+                "enum": ["override", "geoname", "registered_country", "represented_country"],
+                "description": "Which geoname of the network the location comes from, country level for the fallbacks, or a network override"
+            },
+            "confidence": { "$ref": "#/components/schemas/Confidence" },
+            "dataset_version": { "type": "string", "description": "Dataset version that answered, absent for unversioned collections" },
+            "labels": { "type": "array", "items": { "type": "string" }, "description": "Labels of the matching network override" },
+            "is_tor": { "type": "boolean", "description": "The IP is in an imported Tor exit list" },
+            "is_anonymous_proxy": { "type": "boolean", "description": "The IP is in an imported VPN or anonymous proxy list" },
+            "is_hosting": { "type": "boolean", "description": "The IP is in an imported hosting provider list" },
+            "time_zone_details": { "$ref": "#/components/schemas/TimeZoneInfo" },
+            "hostname": { "type": "string", "description": "PTR name, present when requested with `rdns=true` and answered in time" }
+        }
+    }));
+    schemas.insert("HostLookupResponse".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "host": { "type": "string" },
+            "addresses": {
+                "type": "array",
+                "description": "One entry per resolved address, in the resolver's order",
+                "items": {
+                    "oneOf": [
+                        { "$ref": "#/components/schemas/LookupResponse" },
+                        { "$ref": "#/components/schemas/ReservedResponse" },
+                        { "$ref": "#/components/schemas/HostAddressError" }
+                    ]
+                }
+            }
+        }
+    }));
+    schemas.insert("HostAddressError".to_string(), json!({
+        "description": "A resolved address that could not be located, with the error its `ip` lookup returns",
+        "allOf": [
+            { "$ref": "#/components/schemas/Error" },
+            {
+                "type": "object",
+                "required": ["ip"],
+                "properties": { "ip": { "type": "string" } }
+            }
+        ]
+    }));
+    schemas.insert("ReservedResponse".to_string(), json!({
+        "type": "object",
+        "description": "Private, loopback, link-local, CGNAT, multicast, documentation and other IANA special-purpose addresses",
+        "required": ["ip", "type", "network", "purpose", "reference"],
+        "properties": {
+            "ip": { "type": "string" },
+            "type": { "type": "string", "enum": ["reserved"] },
+            "network": { "type": "string", "description": "Special-purpose block containing the IP", "example": "10.0.0.0/8" },
+            "purpose": { "type": "string", "example": "Private-Use" },
+            "reference": { "type": "string", "example": "RFC 1918" }
+        }
+    }));
+    schemas.insert("LookupCoordinates".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "latitude": { "type": "number", "nullable": true },
+            "longitude": { "type": "number", "nullable": true },
+            "accuracy_radius_km": { "type": "integer", "nullable": true }
+        }
+    }));
+    schemas.insert("LookupLocation".to_string(), json!({
+        "type": "object",
+        "required": ["geoname_id"],
+        "properties": {
+            "geoname_id": { "type": "string" },
+            "continent": { "type": "string", "nullable": true },
+            "country": { "type": "string", "nullable": true },
+            "subdivision_1": { "type": "string", "nullable": true },
+            "subdivision_2": { "type": "string", "nullable": true },
+            "city": { "type": "string", "nullable": true },
+            "time_zone": { "type": "string", "nullable": true },
+            "country_details": { "$ref": "#/components/schemas/Country" }
+        }
+    }));
+    schemas.insert("Country".to_string(), json!({
+        "type": "object",
+        "description": "Embedded ISO 3166-1 reference data, absent when the country is unknown",
+        "required": ["alpha2", "alpha3", "numeric", "name", "calling_code", "languages"],
+        "properties": {
+            "alpha2": { "type": "string", "example": "FR" },
+            "alpha3": { "type": "string", "example": "FRA" },
+            "numeric": { "type": "string", "example": "250" },
+            "name": { "type": "string" },
+            "currency": { "type": "string", "description": "ISO 4217 code", "example": "EUR" },
+            "calling_code": { "type": "string", "example": "+33" },
+            "languages": { "type": "array", "items": { "type": "string" }, "description": "ISO 639-1 codes of the primary languages" }
+        }
+    }));
+    schemas.insert("Confidence".to_string(), json!({
+        "type": "object",
+        "description": "How far the location can be trusted, from the accuracy radius, the prefix length of the network and the presence of a city",
+        "required": ["score", "granularity", "city_level"],
+        "properties": {
+            "score": { "type": "integer", "minimum": 0, "maximum": 100 },
+            "granularity": {
+                "type": "string",
+                "enum": ["city", "subdivision", "country", "continent", "unknown"],
+                "description": "Most precise level the location can be trusted at"
+            },
+            "accuracy_radius_km": { "type": "integer", "nullable": true },
+            "prefix_length": { "type": "integer", "nullable": true },
+            "city_level": { "type": "boolean" }
+        }
+    }));
+    schemas.insert("TimeZoneInfo".to_string(), json!({
+        "type": "object",
+        "description": "Present when requested with `tz=true` and the time zone is known",
+        "properties": {
+            "name": { "type": "string" },
+            "utc_offset": { "type": "string", "example": "-04:00" },
+            "utc_offset_seconds": { "type": "integer" },
+            "dst": { "type": "boolean" },
+            "local_time": { "type": "string", "format": "date-time" }
+        }
+    }));
+    schemas.insert("NetworkEntry".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "network": { "type": "string" },
+            "geoname_id": { "type": "string" },
+            "coordinates": { "$ref": "#/components/schemas/LookupCoordinates" }
+        }
+    }));
+    schemas.insert("NetworksResponse".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "geoname_ids": { "type": "array", "items": { "type": "string" } },
+            "total": { "type": "integer" },
+            "page": { "type": "integer" },
+            "per_page": { "type": "integer" },
+            "networks": { "type": "array", "items": { "$ref": "#/components/schemas/NetworkEntry" } }
+        }
+    }));
+    schemas.insert("RangeEntry".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "network": { "type": "string" },
+            "coordinates": { "$ref": "#/components/schemas/LookupCoordinates" },
+            "location": {
+                "nullable": true,
+                "description": "Null when none of the geonames of the network is known",
+                "allOf": [{ "$ref": "#/components/schemas/LookupLocation" }]
+            },
+            "location_source": { "type": "string", "nullable": true, "enum": ["geoname", "registered_country", "represented_country"] }
+        }
+    }));
+    schemas.insert("RangeSummary".to_string(), json!({
+        "type": "object",
+        "description": "Distinct countries and cities of the block, sorted by name",
+        "properties": {
+            "countries": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "iso_code": { "type": "string", "nullable": true },
+                        "name": { "type": "string" }
+                    }
+                }
+            },
+            "cities": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "geoname_id": { "type": "string" },
+                        "name": { "type": "string" },
+                        "country": { "type": "string", "nullable": true }
+                    }
+                }
+            }
+        }
+    }));
+    schemas.insert("RangeResponse".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "cidr": { "type": "string", "description": "Queried block, without host bits" },
+            "total": { "type": "integer" },
+            "page": { "type": "integer" },
+            "per_page": { "type": "integer" },
+            "summary": { "$ref": "#/components/schemas/RangeSummary" },
+            "networks": { "type": "array", "items": { "$ref": "#/components/schemas/RangeEntry" } }
+        }
+    }));
+    schemas.insert("NearestResponse".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "latitude": { "type": "number" },
+            "longitude": { "type": "number" },
+            "distance_km": { "type": "number" },
+            "network": { "type": "string" },
+            "coordinates": { "$ref": "#/components/schemas/LookupCoordinates" },
+            "location": { "$ref": "#/components/schemas/LookupLocation" },
+            "location_source": { "type": "string", "enum": ["geoname", "registered_country", "represented_country"] }
+        }
+    }));
+    schemas.insert("DistanceResponse".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "from": { "$ref": "#/components/schemas/LookupResponse" },
+            "to": {
+                "oneOf": [
+                    { "$ref": "#/components/schemas/LookupResponse" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "latitude": { "type": "number" },
+                            "longitude": { "type": "number" }
+                        }
+                    }
+                ]
+            },
+            "distance_km": { "type": "number" },
+            "accuracy_radius_km": { "type": "integer", "nullable": true, "description": "Sum of both accuracy radii" }
+        }
+    }));
+    schemas.insert("DatasetResponse".to_string(), json!({
+        "type": "object",
+        "required": ["ip_count", "location_count"],
+        "properties": {
+            "version": { "type": "string", "nullable": true },
+            "source": { "type": "string", "nullable": true },
+            "build_date": { "type": "string", "nullable": true },
+            "ip_count": { "type": "integer" },
+            "location_count": { "type": "integer" },
+            "imported_at": { "type": "integer", "nullable": true, "description": "Unix timestamp" },
+            "activated_at": { "type": "integer", "nullable": true, "description": "Unix timestamp" }
+        }
+    }));
+    schemas.insert("Stats".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "cache": { "$ref": "#/components/schemas/CacheStats" },
+            "reverse_dns_cache": { "$ref": "#/components/schemas/CacheStats" }
+        }
+    }));
+    schemas.insert("CacheStats".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "hits": { "type": "integer" },
+            "misses": { "type": "integer" },
+            "entries": { "type": "integer" },
+            "capacity": { "type": "integer" }
+        }
+    }));
+    schemas.insert("NetworkOverride".to_string(), json!({
+        "type": "object",
+        "description": "Custom location of a network, taking precedence over the dataset. The most specific override wins",
+        "required": ["network"],
+        "properties": {
+            "id": { "type": "string", "readOnly": true },
+            "network": { "type": "string", "description": "IPv4 or IPv6 CIDR, without host bits", "example": "203.0.113.0/24" },
+            "labels": { "type": "array", "items": { "type": "string" } },
+            "geoname_id": { "type": "string" },
+            "continent_name": { "type": "string" },
+            "country_iso_code": { "type": "string" },
+            "country_name": { "type": "string" },
+            "subdivision_1_name": { "type": "string" },
+            "subdivision_2_name": { "type": "string" },
+            "city_name": { "type": "string" },
+            "time_zone": { "type": "string" },
+            "latitude": { "type": "number", "nullable": true },
+            "longitude": { "type": "number", "nullable": true },
+            "accuracy_radius": { "type": "integer", "nullable": true, "description": "In kilometers" }
+        }
+    }));
+    schemas.insert("NetworkOverrideList".to_string(), json!({
+        "type": "object",
+        "required": ["total", "page", "per_page", "overrides"],
+        "properties": {
+            "total": { "type": "integer" },
+            "page": { "type": "integer" },
+            "per_page": { "type": "integer" },
+            "overrides": { "type": "array", "items": { "$ref": "#/components/schemas/NetworkOverride" } }
+        }
+    }));
+    schemas.insert("AdminIp".to_string(), json!({
+        "type": "object",
+        "description": "Network record of the active dataset. The octet, netmask, range and centroid fields are derived from `network` and the coordinates on writes",
+        "required": ["network"],
+        "properties": {
+            "id": { "type": "string", "readOnly": true },
+            "network": { "type": "string", "description": "IPv4 CIDR, without host bits", "example": "203.0.113.0/24" },
+            "geoname_id": { "type": "string" },
+            "registered_country_geoname_id": { "type": "string" },
+            "represented_country_geoname_id": { "type": "string" },
+            "latitude": { "type": "string", "example": "45.5" },
+            "longitude": { "type": "string", "example": "-73.6" },
+            "accuracy_radius": { "type": "string", "description": "In kilometers" },
+            "net": { "type": "integer", "readOnly": true },
+            "sub": { "type": "integer", "readOnly": true },
+            "sub2": { "type": "integer", "readOnly": true },
+            "netmask": { "type": "string", "readOnly": true },
+            "centroid": { "type": "object", "readOnly": true },
+            "range_start": { "type": "integer", "readOnly": true },
+            "range_end": { "type": "integer", "readOnly": true }
+        }
+    }));
+    schemas.insert("AdminIpList".to_string(), json!({
+        "type": "object",
+        "required": ["total", "page", "per_page", "ips"],
+        "properties": {
+            "total": { "type": "integer" },
+            "page": { "type": "integer" },
+            "per_page": { "type": "integer" },
+            "ips": { "type": "array", "items": { "$ref": "#/components/schemas/AdminIp" } }
+        }
+    }));
+    schemas.insert("AdminLocation".to_string(), json!({
+        "type": "object",
+        "description": "Location record of the active dataset",
+        "required": ["geoname_id"],
+        "properties": {
+            "id": { "type": "string", "readOnly": true },
+            "geoname_id": { "type": "string", "description": "Unique among the locations" },
+            "continent_name": { "type": "string" },
+            "country_iso_code": { "type": "string", "example": "CA" },
+            "country_name": { "type": "string" },
+            "subdivision_1_name": { "type": "string" },
+            "subdivision_2_name": { "type": "string" },
+            "city_name": { "type": "string" },
+            "time_zone": { "type": "string", "description": "IANA time zone", "example": "America/Toronto" }
+        }
+    }));
+    schemas.insert("AdminLocationList".to_string(), json!({
+        "type": "object",
+        "required": ["total", "page", "per_page", "locations"],
+        "properties": {
+            "total": { "type": "integer" },
+            "page": { "type": "integer" },
+            "per_page": { "type": "integer" },
+            "locations": { "type": "array", "items": { "$ref": "#/components/schemas/AdminLocation" } }
+        }
+    }));
+    schemas.insert("Error".to_string(), json!({
+        "type": "object",
+        "required": ["error"],
+        "properties": {
+            "error": {
+                "type": "object",
+                "required": ["code", "message"],
+                "properties": {
+                    "code": { "type": "string" },
+                    "message": { "type": "string" }
+                }
+            }
+        }
+    }));
+
+    Value::Object(schemas)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controllers::{AdminController, LookupController};
+    use crate::settings::Auth;
+    use super::{document, OPENAPI_PATH};
+
+    /// Makes sure every registered `(method, path)` is described in the document and that the
+    /// document does not describe routes that are not registered.
+    fn check_routes(routes: &[(String, &str)]) -> Result<(), String> {
+        let document = document(&Auth::default().header);
+        let mut problems = Vec::new();
+
+        let paths = match document["paths"].as_object() {
+            Some(paths) => paths,
+            None => return Err("The OpenAPI document has no paths".to_string()),
+        };
+
+        for (method, path) in routes {
+            if paths.get(*path).and_then(|item| item.get(method)).is_none() {
+                problems.push(format!("{} {} is registered but not documented", method.to_uppercase(), path));
+            }
+        }
+
+        for (path, item) in paths {
+            if let Some(operations) = item.as_object() {
+                for method in operations.keys() {
+                    if !routes.iter().any(|(m, p)| m == method && *p == path.as_str()) {
+                        problems.push(format!("{} {} is documented but not registered", method.to_uppercase(), path));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join(", "))
+        }
+    }
+
+    #[test]
+    fn document_describes_every_route() {
+        let routes = LookupController::ROUTES.iter()
+            .chain(AdminController::ROUTES.iter())
+            .map(|route| (route.method.as_str().to_lowercase(), route.path))
+            .chain(Some(("get".to_string(), OPENAPI_PATH)))
+            .collect::<Vec<(String, &str)>>();
+
+        assert_eq!(check_routes(&routes), Ok(()));
+    }
+
+    #[test]
+    fn document_names_the_configured_header() {
+        assert_eq!(document("X-Spotme-Key")["components"]["securitySchemes"]["ApiKeyHeader"]["name"], "X-Spotme-Key");
+    }
+}
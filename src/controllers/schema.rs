@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use crate::confidence::{confidence, Confidence};
+use crate::countries::{find_country, Country};
+use crate::lookup::{LocationSource, LookupResult};
+use crate::models::ip::Ip;
+use crate::models::location::Location;
+use crate::reserved::ReservedRange;
+use crate::threats::ThreatFlags;
+use crate::time_zone::TimeZoneInfo;
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LookupResponse {
+    pub ip: String,
+    pub network: String,
+    pub coordinates: LookupCoordinates,
+    pub location: LookupLocation,
+    /// Which geoname of the network the location comes from
+    pub location_source: LocationSource,
+    pub confidence: Confidence,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset_version: Option<String>,
+    /// Labels of the matching override
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(flatten)]
+    pub threats: ThreatFlags,
+    /// Only computed when requested with `tz=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone_details: Option<TimeZoneInfo>,
+    /// PTR name, only queried when requested with `rdns=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+}
+
+/// Answer of the v2 lookup for special-purpose addresses, which have no location.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReservedResponse {
+    pub ip: String,
+    /// Always `reserved`
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub range: ReservedRange,
+}
+
+impl ReservedResponse {
+    pub fn new(ip: IpAddr, range: &ReservedRange) -> Self {
+        ReservedResponse {
+            ip: ip.to_string(),
+            kind: "reserved",
+            range: *range,
+        }
+    }
+}
+
+/// Answer of the v2 lookup for `host=`, one entry per resolved address.
+#[derive(Serialize, Debug, Clone)]
+pub struct HostLookupResponse {
+    pub host: String,
+    pub addresses: Vec<HostAddress>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum HostAddress {
+    Located(LookupResponse),
+    Reserved(ReservedResponse),
+    Failed(HostAddressError),
+}
+
+/// A resolved address that could not be located, with the error the `ip=` lookup would have returned.
+#[derive(Serialize, Debug, Clone)]
+pub struct HostAddressError {
+    pub ip: String,
+    pub error: ErrorBody,
+}
+
+impl HostAddressError {
+    pub fn new(ip: IpAddr, code: &str, message: String) -> Self {
+        HostAddressError {
+            ip: ip.to_string(),
+            error: ErrorBody {
+                code: code.to_string(),
+                message,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LookupCoordinates {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub accuracy_radius_km: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LookupLocation {
+    pub geoname_id: String,
+    pub continent: Option<String>,
+    pub country: Option<String>,
+    pub subdivision_1: Option<String>,
+    pub subdivision_2: Option<String>,
+    pub city: Option<String>,
+    pub time_zone: Option<String>,
+    /// Reference data of the country, absent when the country is unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_details: Option<&'static Country>,
+}
+
+impl<'a> From<&'a Ip> for LookupCoordinates {
+    fn from(ip: &'a Ip) -> Self {
+        LookupCoordinates {
+            latitude: ip.latitude.parse::<f64>().ok(),
+            longitude: ip.longitude.parse::<f64>().ok(),
+            accuracy_radius_km: ip.accuracy_radius.parse::<u32>().ok(),
+        }
+    }
+}
+
+impl<'a> From<&'a Location> for LookupLocation {
+    fn from(location: &'a Location) -> Self {
+        LookupLocation {
+            geoname_id: location.geoname_id.clone(),
+            continent: non_empty(&location.continent_name),
+            country: non_empty(&location.country_name),
+            subdivision_1: non_empty(&location.subdivision_1_name),
+            subdivision_2: non_empty(&location.subdivision_2_name),
+            city: non_empty(&location.city_name),
+            time_zone: non_empty(&location.time_zone),
+            country_details: find_country(&location.country_iso_code, &location.country_name),
+        }
+    }
+}
+
+impl<'a> From<&'a LookupResult> for LookupResponse {
+    fn from(result: &'a LookupResult) -> Self {
+        LookupResponse {
+            ip: result.request_ip.to_string(),
+            network: result.ip.network.clone(),
+            coordinates: LookupCoordinates::from(&result.ip),
+            location: LookupLocation::from(&result.location),
+            location_source: result.location_source,
+            confidence: confidence(result),
+            dataset_version: result.dataset_version.clone(),
+            labels: result.labels.clone(),
+            threats: result.threats,
+            time_zone_details: None,
+            hostname: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DatasetResponse {
+    /// `None` when the unversioned `ip` and `location` collections are served
+    pub version: Option<String>,
+    pub source: Option<String>,
+    pub build_date: Option<String>,
+    pub ip_count: i64,
+    pub location_count: i64,
+    /// Unix timestamps
+    pub imported_at: Option<i64>,
+    pub activated_at: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NetworkEntry {
+    pub network: String,
+    pub geoname_id: String,
+    pub coordinates: LookupCoordinates,
+}
+
+impl<'a> From<&'a Ip> for NetworkEntry {
+    fn from(ip: &'a Ip) -> Self {
+        NetworkEntry {
+            network: ip.network.clone(),
+            geoname_id: ip.geoname_id.clone(),
+            coordinates: LookupCoordinates::from(ip),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NetworksResponse {
+    /// Locations matching the query
+    pub geoname_ids: Vec<String>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub networks: Vec<NetworkEntry>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NearestResponse {
+    /// Queried point
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Distance between the queried point and the network centroid
+    pub distance_km: f64,
+    pub network: String,
+    pub coordinates: LookupCoordinates,
+    pub location: LookupLocation,
+    pub location_source: LocationSource,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum DistanceTarget {
+    Ip(LookupResponse),
+    Point {
+        latitude: f64,
+        longitude: f64,
+    },
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DistanceResponse {
+    pub from: LookupResponse,
+    pub to: DistanceTarget,
+    /// Great-circle distance between the two coordinates
+    pub distance_km: f64,
+    /// Sum of both accuracy radii, the actual distance is within `distance_km` ± this value
+    pub accuracy_radius_km: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RangeEntry {
+    pub network: String,
+    pub coordinates: LookupCoordinates,
+    pub location: Option<LookupLocation>,
+    pub location_source: Option<LocationSource>,
+}
+
+impl<'a> From<&'a (Ip, Option<(Location, LocationSource)>)> for RangeEntry {
+    fn from(entry: &'a (Ip, Option<(Location, LocationSource)>)) -> Self {
+        let (ip, location) = entry;
+
+        RangeEntry {
+            network: ip.network.clone(),
+            coordinates: LookupCoordinates::from(ip),
+            location: location.as_ref().map(|(location, _)| LookupLocation::from(location)),
+            location_source: location.as_ref().map(|(_, source)| *source),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RangeCountry {
+    pub iso_code: Option<String>,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RangeCity {
+    pub geoname_id: String,
+    pub name: String,
+    pub country: Option<String>,
+}
+
+/// Distinct countries and cities of a range, sorted by name.
+#[derive(Serialize, Debug, Clone)]
+pub struct RangeSummary {
+    pub countries: Vec<RangeCountry>,
+    pub cities: Vec<RangeCity>,
+}
+
+impl<'a> From<&'a [Location]> for RangeSummary {
+    fn from(locations: &'a [Location]) -> Self {
+        let mut countries = BTreeMap::new();
+        let mut cities = BTreeMap::new();
+
+        for location in locations {
+            if !location.country_name.is_empty() {
+                countries.entry((location.country_name.clone(), location.country_iso_code.clone())).or_insert_with(|| RangeCountry {
+                    iso_code: non_empty(&location.country_iso_code),
+                    name: location.country_name.clone(),
+                });
+            }
+
+            if !location.city_name.is_empty() {
+                cities.entry((location.city_name.clone(), location.geoname_id.clone())).or_insert_with(|| RangeCity {
+                    geoname_id: location.geoname_id.clone(),
+                    name: location.city_name.clone(),
+                    country: non_empty(&location.country_name),
+                });
+            }
+        }
+
+        RangeSummary {
+            countries: countries.into_iter().map(|(_, country)| country).collect(),
+            cities: cities.into_iter().map(|(_, city)| city).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RangeResponse {
+    /// Queried block, without host bits
+    pub cidr: String,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub summary: RangeSummary,
+    pub networks: Vec<RangeEntry>,
+}
@@ -0,0 +1,325 @@
+/// ISO 3166-1 countries with the reference data clients otherwise fetch from other services.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Country {
+    pub alpha2: &'static str,
+    pub alpha3: &'static str,
+    /// Zero padded, e.g. `040`
+    pub numeric: &'static str,
+    pub name: &'static str,
+    /// ISO 4217 code, absent for uninhabited territories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<&'static str>,
+    /// International prefix, e.g. `+33`
+    pub calling_code: &'static str,
+    /// ISO 639-1 codes of the primary languages
+    pub languages: &'static [&'static str],
+}
+
+/// Finds a country by ISO code first, the name is only used for locations imported without it.
+pub fn find_country(iso_code: &str, name: &str) -> Option<&'static Country> {
+    if !iso_code.is_empty() {
+        let iso_code = iso_code.to_uppercase();
+
+        if let Ok(index) = COUNTRIES.binary_search_by_key(&iso_code.as_str(), |country| country.alpha2) {
+            return Some(&COUNTRIES[index]);
+        }
+    }
+
+    if name.is_empty() {
+        return None;
+    }
+
+    COUNTRIES.iter().find(|country| country.name.eq_ignore_ascii_case(name))
+}
+
+const fn country(alpha2: &'static str, alpha3: &'static str, numeric: &'static str, name: &'static str, currency: Option<&'static str>, calling_code: &'static str, languages: &'static [&'static str]) -> Country {
+    Country {
+        alpha2,
+        alpha3,
+        numeric,
+        name,
+        currency,
+        calling_code,
+        languages,
+    }
+}
+
+/// Sorted by alpha-2 code
+static COUNTRIES: &[Country] = &[
+    country("AD", "AND", "020", "Andorra", Some("EUR"), "+376", &["ca"]),
+    country("AE", "ARE", "784", "United Arab Emirates", Some("AED"), "+971", &["ar"]),
+    country("AF", "AFG", "004", "Afghanistan", Some("AFN"), "+93", &["ps", "fa"]),
+    country("AG", "ATG", "028", "Antigua and Barbuda", Some("XCD"), "+1268", &["en"]),
+    country("AI", "AIA", "660", "Anguilla", Some("XCD"), "+1264", &["en"]),
+    country("AL", "ALB", "008", "Albania", Some("ALL"), "+355", &["sq"]),
+    country("AM", "ARM", "051", "Armenia", Some("AMD"), "+374", &["hy"]),
+    country("AO", "AGO", "024", "Angola", Some("AOA"), "+244", &["pt"]),
+    country("AQ", "ATA", "010", "Antarctica", None, "+672", &[]),
+    country("AR", "ARG", "032", "Argentina", Some("ARS"), "+54", &["es"]),
+    country("AS", "ASM", "016", "American Samoa", Some("USD"), "+1684", &["en", "sm"]),
+    country("AT", "AUT", "040", "Austria", Some("EUR"), "+43", &["de"]),
+    country("AU", "AUS", "036", "Australia", Some("AUD"), "+61", &["en"]),
+    country("AW", "ABW", "533", "Aruba", Some("AWG"), "+297", &["nl", "pap"]),
+    country("AX", "ALA", "248", "Åland Islands", Some("EUR"), "+358", &["sv"]),
+    country("AZ", "AZE", "031", "Azerbaijan", Some("AZN"), "+994", &["az"]),
+    country("BA", "BIH", "070", "Bosnia and Herzegovina", Some("BAM"), "+387", &["bs", "hr", "sr"]),
+    country("BB", "BRB", "052", "Barbados", Some("BBD"), "+1246", &["en"]),
+    country("BD", "BGD", "050", "Bangladesh", Some("BDT"), "+880", &["bn"]),
+    country("BE", "BEL", "056", "Belgium", Some("EUR"), "+32", &["nl", "fr", "de"]),
+    country("BF", "BFA", "854", "Burkina Faso", Some("XOF"), "+226", &["fr"]),
+    country("BG", "BGR", "100", "Bulgaria", Some("EUR"), "+359", &["bg"]),
+    country("BH", "BHR", "048", "Bahrain", Some("BHD"), "+973", &["ar"]),
+    country("BI", "BDI", "108", "Burundi", Some("BIF"), "+257", &["rn", "fr"]),
+    country("BJ", "BEN", "204", "Benin", Some("XOF"), "+229", &["fr"]),
+    country("BL", "BLM", "652", "Saint Barthélemy", Some("EUR"), "+590", &["fr"]),
+    country("BM", "BMU", "060", "Bermuda", Some("BMD"), "+1441", &["en"]),
+    country("BN", "BRN", "096", "Brunei Darussalam", Some("BND"), "+673", &["ms"]),
+    country("BO", "BOL", "068", "Bolivia", Some("BOB"), "+591", &["es", "qu", "ay"]),
+    country("BQ", "BES", "535", "Bonaire, Sint Eustatius and Saba", Some("USD"), "+599", &["nl"]),
+    country("BR", "BRA", "076", "Brazil", Some("BRL"), "+55", &["pt"]),
+    country("BS", "BHS", "044", "Bahamas", Some("BSD"), "+1242", &["en"]),
+    country("BT", "BTN", "064", "Bhutan", Some("BTN"), "+975", &["dz"]),
+    country("BV", "BVT", "074", "Bouvet Island", Some("NOK"), "+47", &[]),
+    country("BW", "BWA", "072", "Botswana", Some("BWP"), "+267", &["en", "tn"]),
+    country("BY", "BLR", "112", "Belarus", Some("BYN"), "+375", &["be", "ru"]),
+    country("BZ", "BLZ", "084", "Belize", Some("BZD"), "+501", &["en"]),
+    country("CA", "CAN", "124", "Canada", Some("CAD"), "+1", &["en", "fr"]),
+    country("CC", "CCK", "166", "Cocos (Keeling) Islands", Some("AUD"), "+61", &["en"]),
+    country("CD", "COD", "180", "Congo, The Democratic Republic of the", Some("CDF"), "+243", &["fr"]),
+    country("CF", "CAF", "140", "Central African Republic", Some("XAF"), "+236", &["fr", "sg"]),
+    country("CG", "COG", "178", "Congo", Some("XAF"), "+242", &["fr"]),
+    country("CH", "CHE", "756", "Switzerland", Some("CHF"), "+41", &["de", "fr", "it", "rm"]),
+    country("CI", "CIV", "384", "Côte d'Ivoire", Some("XOF"), "+225", &["fr"]),
+    country("CK", "COK", "184", "Cook Islands", Some("NZD"), "+682", &["en"]),
+    country("CL", "CHL", "152", "Chile", Some("CLP"), "+56", &["es"]),
+    country("CM", "CMR", "120", "Cameroon", Some("XAF"), "+237", &["fr", "en"]),
+    country("CN", "CHN", "156", "China", Some("CNY"), "+86", &["zh"]),
+    country("CO", "COL", "170", "Colombia", Some("COP"), "+57", &["es"]),
+    country("CR", "CRI", "188", "Costa Rica", Some("CRC"), "+506", &["es"]),
+    country("CU", "CUB", "192", "Cuba", Some("CUP"), "+53", &["es"]),
+    country("CV", "CPV", "132", "Cabo Verde", Some("CVE"), "+238", &["pt"]),
+    country("CW", "CUW", "531", "Curaçao", Some("XCG"), "+599", &["nl", "pap"]),
+    country("CX", "CXR", "162", "Christmas Island", Some("AUD"), "+61", &["en"]),
+    country("CY", "CYP", "196", "Cyprus", Some("EUR"), "+357", &["el", "tr"]),
+    country("CZ", "CZE", "203", "Czechia", Some("CZK"), "+420", &["cs"]),
+    country("DE", "DEU", "276", "Germany", Some("EUR"), "+49", &["de"]),
+    country("DJ", "DJI", "262", "Djibouti", Some("DJF"), "+253", &["fr", "ar"]),
+    country("DK", "DNK", "208", "Denmark", Some("DKK"), "+45", &["da"]),
+    country("DM", "DMA", "212", "Dominica", Some("XCD"), "+1767", &["en"]),
+    country("DO", "DOM", "214", "Dominican Republic", Some("DOP"), "+1809", &["es"]),
+    country("DZ", "DZA", "012", "Algeria", Some("DZD"), "+213", &["ar"]),
+    country("EC", "ECU", "218", "Ecuador", Some("USD"), "+593", &["es"]),
+    country("EE", "EST", "233", "Estonia", Some("EUR"), "+372", &["et"]),
+    country("EG", "EGY", "818", "Egypt", Some("EGP"), "+20", &["ar"]),
+    country("EH", "ESH", "732", "Western Sahara", Some("MAD"), "+212", &["ar"]),
+    country("ER", "ERI", "232", "Eritrea", Some("ERN"), "+291", &["ti", "ar", "en"]),
+    country("ES", "ESP", "724", "Spain", Some("EUR"), "+34", &["es"]),
+    country("ET", "ETH", "231", "Ethiopia", Some("ETB"), "+251", &["am"]),
+    country("FI", "FIN", "246", "Finland", Some("EUR"), "+358", &["fi", "sv"]),
+    country("FJ", "FJI", "242", "Fiji", Some("FJD"), "+679", &["en", "fj"]),
+    country("FK", "FLK", "238", "Falkland Islands (Malvinas)", Some("FKP"), "+500", &["en"]),
+    country("FM", "FSM", "583", "Micronesia, Federated States of", Some("USD"), "+691", &["en"]),
+    country("FO", "FRO", "234", "Faroe Islands", Some("DKK"), "+298", &["fo"]),
+    country("FR", "FRA", "250", "France", Some("EUR"), "+33", &["fr"]),
+    country("GA", "GAB", "266", "Gabon", Some("XAF"), "+241", &["fr"]),
+    country("GB", "GBR", "826", "United Kingdom", Some("GBP"), "+44", &["en"]),
+    country("GD", "GRD", "308", "Grenada", Some("XCD"), "+1473", &["en"]),
+    country("GE", "GEO", "268", "Georgia", Some("GEL"), "+995", &["ka"]),
+    country("GF", "GUF", "254", "French Guiana", Some("EUR"), "+594", &["fr"]),
+    country("GG", "GGY", "831", "Guernsey", Some("GBP"), "+44", &["en"]),
+    country("GH", "GHA", "288", "Ghana", Some("GHS"), "+233", &["en"]),
+    country("GI", "GIB", "292", "Gibraltar", Some("GIP"), "+350", &["en"]),
+    country("GL", "GRL", "304", "Greenland", Some("DKK"), "+299", &["kl"]),
+    country("GM", "GMB", "270", "Gambia", Some("GMD"), "+220", &["en"]),
+    country("GN", "GIN", "324", "Guinea", Some("GNF"), "+224", &["fr"]),
+    country("GP", "GLP", "312", "Guadeloupe", Some("EUR"), "+590", &["fr"]),
+    country("GQ", "GNQ", "226", "Equatorial Guinea", Some("XAF"), "+240", &["es", "fr"]),
+    country("GR", "GRC", "300", "Greece", Some("EUR"), "+30", &["el"]),
+    country("GS", "SGS", "239", "South Georgia and the South Sandwich Islands", Some("GBP"), "+500", &["en"]),
+    country("GT", "GTM", "320", "Guatemala", Some("GTQ"), "+502", &["es"]),
+    country("GU", "GUM", "316", "Guam", Some("USD"), "+1671", &["en", "ch"]),
+    country("GW", "GNB", "624", "Guinea-Bissau", Some("XOF"), "+245", &["pt"]),
+    country("GY", "GUY", "328", "Guyana", Some("GYD"), "+592", &["en"]),
+    country("HK", "HKG", "344", "Hong Kong", Some("HKD"), "+852", &["zh", "en"]),
+    country("HM", "HMD", "334", "Heard Island and McDonald Islands", Some("AUD"), "+672", &[]),
+    country("HN", "HND", "340", "Honduras", Some("HNL"), "+504", &["es"]),
+    country("HR", "HRV", "191", "Croatia", Some("EUR"), "+385", &["hr"]),
+    country("HT", "HTI", "332", "Haiti", Some("HTG"), "+509", &["fr", "ht"]),
+    country("HU", "HUN", "348", "Hungary", Some("HUF"), "+36", &["hu"]),
+    country("ID", "IDN", "360", "Indonesia", Some("IDR"), "+62", &["id"]),
+    country("IE", "IRL", "372", "Ireland", Some("EUR"), "+353", &["en", "ga"]),
+    country("IL", "ISR", "376", "Israel", Some("ILS"), "+972", &["he", "ar"]),
+    country("IM", "IMN", "833", "Isle of Man", Some("GBP"), "+44", &["en", "gv"]),
+    country("IN", "IND", "356", "India", Some("INR"), "+91", &["hi", "en"]),
+    country("IO", "IOT", "086", "British Indian Ocean Territory", Some("USD"), "+246", &["en"]),
+    country("IQ", "IRQ", "368", "Iraq", Some("IQD"), "+964", &["ar", "ku"]),
+    country("IR", "IRN", "364", "Iran", Some("IRR"), "+98", &["fa"]),
+    country("IS", "ISL", "352", "Iceland", Some("ISK"), "+354", &["is"]),
+    country("IT", "ITA", "380", "Italy", Some("EUR"), "+39", &["it"]),
+    country("JE", "JEY", "832", "Jersey", Some("GBP"), "+44", &["en"]),
+    country("JM", "JAM", "388", "Jamaica", Some("JMD"), "+1876", &["en"]),
+    country("JO", "JOR", "400", "Jordan", Some("JOD"), "+962", &["ar"]),
+    country("JP", "JPN", "392", "Japan", Some("JPY"), "+81", &["ja"]),
+    country("KE", "KEN", "404", "Kenya", Some("KES"), "+254", &["sw", "en"]),
+    country("KG", "KGZ", "417", "Kyrgyzstan", Some("KGS"), "+996", &["ky", "ru"]),
+    country("KH", "KHM", "116", "Cambodia", Some("KHR"), "+855", &["km"]),
+    country("KI", "KIR", "296", "Kiribati", Some("AUD"), "+686", &["en"]),
+    country("KM", "COM", "174", "Comoros", Some("KMF"), "+269", &["ar", "fr"]),
+    country("KN", "KNA", "659", "Saint Kitts and Nevis", Some("XCD"), "+1869", &["en"]),
+    country("KP", "PRK", "408", "North Korea", Some("KPW"), "+850", &["ko"]),
+    country("KR", "KOR", "410", "South Korea", Some("KRW"), "+82", &["ko"]),
+    country("KW", "KWT", "414", "Kuwait", Some("KWD"), "+965", &["ar"]),
+    country("KY", "CYM", "136", "Cayman Islands", Some("KYD"), "+1345", &["en"]),
+    country("KZ", "KAZ", "398", "Kazakhstan", Some("KZT"), "+7", &["kk", "ru"]),
+    country("LA", "LAO", "418", "Laos", Some("LAK"), "+856", &["lo"]),
+    country("LB", "LBN", "422", "Lebanon", Some("LBP"), "+961", &["ar"]),
+    country("LC", "LCA", "662", "Saint Lucia", Some("XCD"), "+1758", &["en"]),
+    country("LI", "LIE", "438", "Liechtenstein", Some("CHF"), "+423", &["de"]),
+    country("LK", "LKA", "144", "Sri Lanka", Some("LKR"), "+94", &["si", "ta"]),
+    country("LR", "LBR", "430", "Liberia", Some("LRD"), "+231", &["en"]),
+    country("LS", "LSO", "426", "Lesotho", Some("LSL"), "+266", &["en", "st"]),
+    country("LT", "LTU", "440", "Lithuania", Some("EUR"), "+370", &["lt"]),
+    country("LU", "LUX", "442", "Luxembourg", Some("EUR"), "+352", &["lb", "fr", "de"]),
+    country("LV", "LVA", "428", "Latvia", Some("EUR"), "+371", &["lv"]),
+    country("LY", "LBY", "434", "Libya", Some("LYD"), "+218", &["ar"]),
+    country("MA", "MAR", "504", "Morocco", Some("MAD"), "+212", &["ar", "fr"]),
+    country("MC", "MCO", "492", "Monaco", Some("EUR"), "+377", &["fr"]),
+    country("MD", "MDA", "498", "Moldova", Some("MDL"), "+373", &["ro"]),
+    country("ME", "MNE", "499", "Montenegro", Some("EUR"), "+382", &["sr"]),
+    country("MF", "MAF", "663", "Saint Martin (French part)", Some("EUR"), "+590", &["fr"]),
+    country("MG", "MDG", "450", "Madagascar", Some("MGA"), "+261", &["mg", "fr"]),
+    country("MH", "MHL", "584", "Marshall Islands", Some("USD"), "+692", &["en", "mh"]),
+    country("MK", "MKD", "807", "North Macedonia", Some("MKD"), "+389", &["mk"]),
+    country("ML", "MLI", "466", "Mali", Some("XOF"), "+223", &["fr"]),
+    country("MM", "MMR", "104", "Myanmar", Some("MMK"), "+95", &["my"]),
+    country("MN", "MNG", "496", "Mongolia", Some("MNT"), "+976", &["mn"]),
+    country("MO", "MAC", "446", "Macao", Some("MOP"), "+853", &["zh", "pt"]),
+    country("MP", "MNP", "580", "Northern Mariana Islands", Some("USD"), "+1670", &["en", "ch"]),
+    country("MQ", "MTQ", "474", "Martinique", Some("EUR"), "+596", &["fr"]),
+    country("MR", "MRT", "478", "Mauritania", Some("MRU"), "+222", &["ar"]),
+    country("MS", "MSR", "500", "Montserrat", Some("XCD"), "+1664", &["en"]),
+    country("MT", "MLT", "470", "Malta", Some("EUR"), "+356", &["mt", "en"]),
+    country("MU", "MUS", "480", "Mauritius", Some("MUR"), "+230", &["en", "fr"]),
+    country("MV", "MDV", "462", "Maldives", Some("MVR"), "+960", &["dv"]),
+    country("MW", "MWI", "454", "Malawi", Some("MWK"), "+265", &["en", "ny"]),
+    country("MX", "MEX", "484", "Mexico", Some("MXN"), "+52", &["es"]),
+    country("MY", "MYS", "458", "Malaysia", Some("MYR"), "+60", &["ms"]),
+    country("MZ", "MOZ", "508", "Mozambique", Some("MZN"), "+258", &["pt"]),
+    country("NA", "NAM", "516", "Namibia", Some("NAD"), "+264", &["en"]),
+    country("NC", "NCL", "540", "New Caledonia", Some("XPF"), "+687", &["fr"]),
+    country("NE", "NER", "562", "Niger", Some("XOF"), "+227", &["fr"]),
+    country("NF", "NFK", "574", "Norfolk Island", Some("AUD"), "+672", &["en"]),
+    country("NG", "NGA", "566", "Nigeria", Some("NGN"), "+234", &["en"]),
+    country("NI", "NIC", "558", "Nicaragua", Some("NIO"), "+505", &["es"]),
+    country("NL", "NLD", "528", "Netherlands", Some("EUR"), "+31", &["nl"]),
+    country("NO", "NOR", "578", "Norway", Some("NOK"), "+47", &["no", "nb", "nn"]),
+    country("NP", "NPL", "524", "Nepal", Some("NPR"), "+977", &["ne"]),
+    country("NR", "NRU", "520", "Nauru", Some("AUD"), "+674", &["en", "na"]),
+    country("NU", "NIU", "570", "Niue", Some("NZD"), "+683", &["en"]),
+    country("NZ", "NZL", "554", "New Zealand", Some("NZD"), "+64", &["en", "mi"]),
+    country("OM", "OMN", "512", "Oman", Some("OMR"), "+968", &["ar"]),
+    country("PA", "PAN", "591", "Panama", Some("PAB"), "+507", &["es"]),
+    country("PE", "PER", "604", "Peru", Some("PEN"), "+51", &["es", "qu"]),
+    country("PF", "PYF", "258", "French Polynesia", Some("XPF"), "+689", &["fr"]),
+    country("PG", "PNG", "598", "Papua New Guinea", Some("PGK"), "+675", &["en"]),
+    country("PH", "PHL", "608", "Philippines", Some("PHP"), "+63", &["en", "tl"]),
+    country("PK", "PAK", "586", "Pakistan", Some("PKR"), "+92", &["ur", "en"]),
+    country("PL", "POL", "616", "Poland", Some("PLN"), "+48", &["pl"]),
+    country("PM", "SPM", "666", "Saint Pierre and Miquelon", Some("EUR"), "+508", &["fr"]),
+    country("PN", "PCN", "612", "Pitcairn", Some("NZD"), "+64", &["en"]),
+    country("PR", "PRI", "630", "Puerto Rico", Some("USD"), "+1787", &["es", "en"]),
+    country("PS", "PSE", "275", "Palestine, State of", Some("ILS"), "+970", &["ar"]),
+    country("PT", "PRT", "620", "Portugal", Some("EUR"), "+351", &["pt"]),
+    country("PW", "PLW", "585", "Palau", Some("USD"), "+680", &["en"]),
+    country("PY", "PRY", "600", "Paraguay", Some("PYG"), "+595", &["es", "gn"]),
+    country("QA", "QAT", "634", "Qatar", Some("QAR"), "+974", &["ar"]),
+    country("RE", "REU", "638", "Réunion", Some("EUR"), "+262", &["fr"]),
+    country("RO", "ROU", "642", "Romania", Some("RON"), "+40", &["ro"]),
+    country("RS", "SRB", "688", "Serbia", Some("RSD"), "+381", &["sr"]),
+    country("RU", "RUS", "643", "Russian Federation", Some("RUB"), "+7", &["ru"]),
+    country("RW", "RWA", "646", "Rwanda", Some("RWF"), "+250", &["rw", "en", "fr"]),
+    country("SA", "SAU", "682", "Saudi Arabia", Some("SAR"), "+966", &["ar"]),
+    country("SB", "SLB", "090", "Solomon Islands", Some("SBD"), "+677", &["en"]),
+    country("SC", "SYC", "690", "Seychelles", Some("SCR"), "+248", &["fr", "en"]),
+    country("SD", "SDN", "729", "Sudan", Some("SDG"), "+249", &["ar", "en"]),
+    country("SE", "SWE", "752", "Sweden", Some("SEK"), "+46", &["sv"]),
+    country("SG", "SGP", "702", "Singapore", Some("SGD"), "+65", &["en", "ms", "ta", "zh"]),
+    country("SH", "SHN", "654", "Saint Helena, Ascension and Tristan da Cunha", Some("SHP"), "+290", &["en"]),
+    country("SI", "SVN", "705", "Slovenia", Some("EUR"), "+386", &["sl"]),
+    country("SJ", "SJM", "744", "Svalbard and Jan Mayen", Some("NOK"), "+47", &["no"]),
+    country("SK", "SVK", "703", "Slovakia", Some("EUR"), "+421", &["sk"]),
+    country("SL", "SLE", "694", "Sierra Leone", Some("SLE"), "+232", &["en"]),
+    country("SM", "SMR", "674", "San Marino", Some("EUR"), "+378", &["it"]),
+    country("SN", "SEN", "686", "Senegal", Some("XOF"), "+221", &["fr"]),
+    country("SO", "SOM", "706", "Somalia", Some("SOS"), "+252", &["so", "ar"]),
+    country("SR", "SUR", "740", "Suriname", Some("SRD"), "+597", &["nl"]),
+    country("SS", "SSD", "728", "South Sudan", Some("SSP"), "+211", &["en"]),
+    country("ST", "STP", "678", "Sao Tome and Principe", Some("STN"), "+239", &["pt"]),
+    country("SV", "SLV", "222", "El Salvador", Some("USD"), "+503", &["es"]),
+    country("SX", "SXM", "534", "Sint Maarten (Dutch part)", Some("XCG"), "+1721", &["nl", "en"]),
+    country("SY", "SYR", "760", "Syria", Some("SYP"), "+963", &["ar"]),
+    country("SZ", "SWZ", "748", "Eswatini", Some("SZL"), "+268", &["en", "ss"]),
+    country("TC", "TCA", "796", "Turks and Caicos Islands", Some("USD"), "+1649", &["en"]),
+    country("TD", "TCD", "148", "Chad", Some("XAF"), "+235", &["fr", "ar"]),
+    country("TF", "ATF", "260", "French Southern Territories", Some("EUR"), "+262", &["fr"]),
+    country("TG", "TGO", "768", "Togo", Some("XOF"), "+228", &["fr"]),
+    country("TH", "THA", "764", "Thailand", Some("THB"), "+66", &["th"]),
+    country("TJ", "TJK", "762", "Tajikistan", Some("TJS"), "+992", &["tg", "ru"]),
+    country("TK", "TKL", "772", "Tokelau", Some("NZD"), "+690", &["en"]),
+    country("TL", "TLS", "626", "Timor-Leste", Some("USD"), "+670", &["pt"]),
+    country("TM", "TKM", "795", "Turkmenistan", Some("TMT"), "+993", &["tk", "ru"]),
+    country("TN", "TUN", "788", "Tunisia", Some("TND"), "+216", &["ar"]),
+    country("TO", "TON", "776", "Tonga", Some("TOP"), "+676", &["en", "to"]),
+    country("TR", "TUR", "792", "Türkiye", Some("TRY"), "+90", &["tr"]),
+    country("TT", "TTO", "780", "Trinidad and Tobago", Some("TTD"), "+1868", &["en"]),
+    country("TV", "TUV", "798", "Tuvalu", Some("AUD"), "+688", &["en"]),
+    country("TW", "TWN", "158", "Taiwan", Some("TWD"), "+886", &["zh"]),
+    country("TZ", "TZA", "834", "Tanzania", Some("TZS"), "+255", &["sw", "en"]),
+    country("UA", "UKR", "804", "Ukraine", Some("UAH"), "+380", &["uk"]),
+    country("UG", "UGA", "800", "Uganda", Some("UGX"), "+256", &["en", "sw"]),
+    country("UM", "UMI", "581", "United States Minor Outlying Islands", Some("USD"), "+1", &["en"]),
+    country("US", "USA", "840", "United States", Some("USD"), "+1", &["en"]),
+    country("UY", "URY", "858", "Uruguay", Some("UYU"), "+598", &["es"]),
+    country("UZ", "UZB", "860", "Uzbekistan", Some("UZS"), "+998", &["uz"]),
+    country("VA", "VAT", "336", "Holy See (Vatican City State)", Some("EUR"), "+379", &["it", "la"]),
+    country("VC", "VCT", "670", "Saint Vincent and the Grenadines", Some("XCD"), "+1784", &["en"]),
+    country("VE", "VEN", "862", "Venezuela", Some("VES"), "+58", &["es"]),
+    country("VG", "VGB", "092", "Virgin Islands, British", Some("USD"), "+1284", &["en"]),
+    country("VI", "VIR", "850", "Virgin Islands, U.S.", Some("USD"), "+1340", &["en"]),
+    country("VN", "VNM", "704", "Vietnam", Some("VND"), "+84", &["vi"]),
+    country("VU", "VUT", "548", "Vanuatu", Some("VUV"), "+678", &["bi", "en", "fr"]),
+    country("WF", "WLF", "876", "Wallis and Futuna", Some("XPF"), "+681", &["fr"]),
+    country("WS", "WSM", "882", "Samoa", Some("WST"), "+685", &["sm", "en"]),
+    country("YE", "YEM", "887", "Yemen", Some("YER"), "+967", &["ar"]),
+    country("YT", "MYT", "175", "Mayotte", Some("EUR"), "+262", &["fr"]),
+    country("ZA", "ZAF", "710", "South Africa", Some("ZAR"), "+27", &["zu", "xh", "af", "en"]),
+    country("ZM", "ZMB", "894", "Zambia", Some("ZMW"), "+260", &["en"]),
+    country("ZW", "ZWE", "716", "Zimbabwe", Some("ZWG"), "+263", &["en", "sn", "nd"]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countries_are_sorted_and_unique_by_code() {
+        for pair in COUNTRIES.windows(2) {
+            assert!(pair[0].alpha2 < pair[1].alpha2, "{} must come before {}", pair[0].alpha2, pair[1].alpha2);
+        }
+    }
+
+    #[test]
+    fn finds_countries_by_code_then_name() {
+        assert_eq!(find_country("fr", "").map(|country| country.alpha3), Some("FRA"));
+        assert_eq!(find_country("", "bulgaria").map(|country| country.alpha2), Some("BG"));
+        assert_eq!(find_country("", "").map(|country| country.alpha2), None);
+        assert_eq!(find_country("XX", "").map(|country| country.alpha2), None);
+    }
+
+    #[test]
+    fn currencies_follow_the_latest_changeovers() {
+        assert_eq!(find_country("BG", "").and_then(|country| country.currency), Some("EUR"));
+        assert_eq!(find_country("CW", "").and_then(|country| country.currency), Some("XCG"));
+        assert_eq!(find_country("SX", "").and_then(|country| country.currency), Some("XCG"));
+    }
+}
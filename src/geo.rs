@@ -0,0 +1,91 @@
+use bson::{Bson, from_bson};
+use crate::models::{Repository, RepositoryCollection, RepositoryError};
+use crate::models::ip::Ip;
+
+/// Mean radius of the Earth, in kilometers
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+pub fn valid_coordinates(latitude: f64, longitude: f64) -> bool {
+    latitude.abs() <= 90.0 && longitude.abs() <= 180.0
+}
+
+/// Great-circle distance between two points, in kilometers
+pub fn haversine_km(from_latitude: f64, from_longitude: f64, to_latitude: f64, to_longitude: f64) -> f64 {
+    let delta_latitude = (to_latitude - from_latitude).to_radians();
+    let delta_longitude = (to_longitude - from_longitude).to_radians();
+
+    let a = (delta_latitude / 2.0).sin().powi(2)
+        + from_latitude.to_radians().cos() * to_latitude.to_radians().cos() * (delta_longitude / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// Network whose centroid is the closest to the given point, with the distance to it in kilometers.
+/// Relies on the `2dsphere` index of the `ip` collection, networks without centroid are never returned.
+pub fn nearest_network(repos: &RepositoryCollection, latitude: f64, longitude: f64) -> Result<Option<(Ip, f64)>, RepositoryError> {
+    let pipeline = vec![
+        doc! {
+            "$geoNear": {
+                "near": { "type": "Point", "coordinates": [longitude, latitude] },
+                "distanceField": "distance",
+                "spherical": true
+            }
+        },
+        doc! {"$limit": 1},
+    ];
+
+    // Aggregated directly, `find_with_pipeline` would report a missing index as no network at all
+    let nearest = repos.ip.get_collection()?.aggregate(pipeline, None)?.next().transpose()?;
+
+    if let Some(document) = nearest {
+        let distance_m = document.get_f64("distance").unwrap_or(0.0);
+        let ip = from_bson::<Ip>(Bson::Document(document))?;
+        return Ok(Some((ip, distance_m / 1000.0)));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() <= tolerance, "{} is not within {} of {}", actual, tolerance, expected);
+    }
+
+    #[test]
+    fn measures_known_city_pairs() {
+        // Paris to London, and New York to Los Angeles
+        assert_close(haversine_km(48.8566, 2.3522, 51.5074, -0.1278), 343.6, 0.5);
+        assert_close(haversine_km(40.7128, -74.0060, 34.0522, -118.2437), 3935.8, 0.5);
+        assert_close(haversine_km(51.5074, -0.1278, 48.8566, 2.3522), haversine_km(48.8566, 2.3522, 51.5074, -0.1278), 1e-9);
+    }
+
+    #[test]
+    fn measures_zero_and_antipodal_distances() {
+        assert_close(haversine_km(48.8566, 2.3522, 48.8566, 2.3522), 0.0, 1e-9);
+        assert_close(haversine_km(0.0, 0.0, 0.0, 180.0), PI * EARTH_RADIUS_KM, 1e-6);
+        assert_close(haversine_km(90.0, 0.0, -90.0, 0.0), PI * EARTH_RADIUS_KM, 1e-6);
+    }
+
+    #[test]
+    fn accepts_coordinates_up_to_their_bounds() {
+        assert!(valid_coordinates(0.0, 0.0));
+        assert!(valid_coordinates(90.0, 180.0));
+        assert!(valid_coordinates(-90.0, -180.0));
+    }
+
+    #[test]
+    fn rejects_out_of_range_and_non_finite_coordinates() {
+        assert!(!valid_coordinates(90.1, 0.0));
+        assert!(!valid_coordinates(-90.1, 0.0));
+        assert!(!valid_coordinates(0.0, 180.1));
+        assert!(!valid_coordinates(0.0, -180.1));
+        assert!(!valid_coordinates(f64::NAN, 0.0));
+        assert!(!valid_coordinates(0.0, f64::NAN));
+        assert!(!valid_coordinates(f64::INFINITY, 0.0));
+        assert!(!valid_coordinates(0.0, f64::NEG_INFINITY));
+    }
+}
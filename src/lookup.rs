@@ -0,0 +1,312 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use ipnet::IpNet;
+use mongodb::coll::options::FindOptions;
+use crate::cache::{CacheStats, TtlCache};
+use crate::models::{Repository, RepositoryCollection, RepositoryError};
+use crate::models::ip::Ip;
+use crate::models::location::Location;
+use crate::models::network_override::NetworkOverride;
+use crate::overrides::{override_location, override_network, OverrideTable};
+use crate::reserved::{reserved_range, ReservedRange};
+use crate::resolver::{ResolveError, Resolver, ReverseDns};
+use crate::settings::{self, Cache};
+use crate::threats::{threat_flags, ThreatFlags};
+
+#[derive(Debug)]
+pub enum LookupError {
+    /// Special-purpose address, answered without querying the database
+    Reserved(&'static ReservedRange),
+    UnsupportedAddress,
+    NetworkNotFound,
+    LocationNotFound,
+    Repository(RepositoryError),
+}
+
+impl From<RepositoryError> for LookupError {
+    fn from(e: RepositoryError) -> Self {
+        LookupError::Repository(e)
+    }
+}
+
+/// Which geoname of the matching network the location was found with, from the most to the least precise.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationSource {
+    /// Custom location of the `overrides` collection or file
+    Override,
+    Geoname,
+    RegisteredCountry,
+    RepresentedCountry,
+}
+
+/// A resolved lookup: the network record that matched the address and the location it points to.
+#[derive(Debug, Clone)]
+pub struct LookupResult {
+    pub request_ip: IpAddr,
+    pub ip: Ip,
+    pub location: Location,
+    pub location_source: LocationSource,
+    /// Dataset version that answered, `None` for the unversioned collections
+    pub dataset_version: Option<String>,
+    /// Labels of the matching override
+    pub labels: Vec<String>,
+    pub threats: ThreatFlags,
+}
+
+/// Entry point of every lookup: resolves addresses against the repositories, keeping hot results in memory.
+#[derive(Clone)]
+pub struct Locator {
+    pub repos: RepositoryCollection,
+    overrides: OverrideTable,
+    resolver: Arc<dyn Resolver>,
+    reverse_dns: ReverseDns,
+    cache: TtlCache<IpAddr, LookupResult>,
+}
+
+impl Locator {
+    pub fn new(repos: RepositoryCollection, overrides: OverrideTable, resolver: Arc<dyn Resolver>, resolver_config: &settings::Resolver, cache: &Cache) -> Self {
+        Locator {
+            repos,
+            overrides,
+            reverse_dns: ReverseDns::new(resolver.clone(), resolver_config),
+            resolver,
+            cache: TtlCache::new(cache.max_entries, Duration::from_secs(cache.ttl_secs)),
+        }
+    }
+
+    pub fn lookup(&self, addr: IpAddr) -> Result<LookupResult, LookupError> {
+        if let Some(result) = self.cache.get(&addr) {
+            return Ok(result);
+        }
+
+        // Overrides may cover reserved and IPv6 addresses as well, so they are matched first
+        let result = match self.overrides.find(&addr) {
+            Some((net, network_override)) => LookupResult {
+                request_ip: addr,
+                ip: override_network(&net, &network_override),
+                location: override_location(&network_override),
+                location_source: LocationSource::Override,
+                dataset_version: self.repos.dataset_version(),
+                labels: network_override.labels,
+                threats: threat_flags(&self.repos, &addr)?,
+            },
+            None => lookup_ip(&self.repos, addr)?,
+        };
+
+        self.cache.insert(addr, result.clone());
+
+        Ok(result)
+    }
+
+    /// Looks up every address `host` resolves to, in the resolver's order.
+    /// Each address succeeds or fails on its own, only resolution errors fail the whole lookup.
+    pub fn lookup_host(&self, host: &str) -> Result<Vec<(IpAddr, Result<LookupResult, LookupError>)>, ResolveError> {
+        let addrs = self.resolver.resolve(host)?;
+
+        Ok(addrs.into_iter().map(|addr| (addr, self.lookup(addr))).collect())
+    }
+
+    /// The override answering for `addr`, if any.
+    pub fn find_override(&self, addr: &IpAddr) -> Option<(IpNet, NetworkOverride)> {
+        self.overrides.find(addr)
+    }
+
+    /// PTR name of `addr`, `None` when it has none or the resolver is too slow.
+    /// Kept out of `lookup` so that the lookup cache does not depend on DNS.
+    pub fn hostname(&self, addr: IpAddr) -> Option<String> {
+        self.reverse_dns.hostname(addr)
+    }
+
+    /// Drops every cached result, to be called whenever the underlying data changes.
+    pub fn invalidate(&self) {
+        self.cache.clear();
+    }
+
+    /// To be called after writing to the `ip` or `location` collection, the other instances drop their cache
+    /// on their next poll of the data revision.
+    pub fn data_changed(&self) -> Result<(), RepositoryError> {
+        self.invalidate();
+        self.repos.bump_data_revision()
+    }
+
+    /// To be called after writing to the `overrides` collection.
+    pub fn reload_overrides(&self) -> Result<(), RepositoryError> {
+        if self.overrides.reload()? {
+            self.invalidate();
+        }
+
+        Ok(())
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    pub fn reverse_dns_stats(&self) -> CacheStats {
+        self.reverse_dns.cache_stats()
+    }
+
+    /// Polls the `metadata` collection and switches to a newly activated dataset without a restart.
+    /// Overrides and networks written through other instances and threat list imports are picked up on the same schedule.
+    pub fn watch_dataset(&self, interval: Duration) -> thread::JoinHandle<()> {
+        let locator = self.clone();
+        let mut threats_revision = self.repos.threats_revision().unwrap_or(0);
+        let mut data_revision = self.repos.data_revision().unwrap_or(0);
+
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+
+            match locator.repos.refresh_dataset() {
+                Ok(true) => {
+                    info!("Now serving dataset {}", locator.repos.dataset_version().unwrap_or_else(|| "(unversioned)".to_string()));
+                    locator.invalidate();
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Unable to refresh the active dataset: {:?}", e),
+            }
+
+            if let Err(e) = locator.reload_overrides() {
+                warn!("Unable to reload the network overrides: {:?}", e);
+            }
+
+            // Cached results carry the threat flags of the lists at the time of the lookup
+            match locator.repos.threats_revision() {
+                Ok(revision) if revision != threats_revision => {
+                    info!("Threat lists changed, dropping the cached lookups");
+                    threats_revision = revision;
+                    locator.invalidate();
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Unable to check the threat lists revision: {:?}", e),
+            }
+
+            match locator.repos.data_revision() {
+                Ok(revision) if revision != data_revision => {
+                    info!("Networks or locations were edited, dropping the cached lookups");
+                    data_revision = revision;
+                    locator.invalidate();
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Unable to check the data revision: {:?}", e),
+            }
+        })
+    }
+}
+
+pub fn lookup_ip(repos: &RepositoryCollection, addr: IpAddr) -> Result<LookupResult, LookupError> {
+    if let Some(range) = reserved_range(&addr) {
+        return Err(LookupError::Reserved(range));
+    }
+
+    let addr_v4 = match addr {
+        IpAddr::V4(addr_v4) => addr_v4,
+        IpAddr::V6(_) => return Err(LookupError::UnsupportedAddress),
+    };
+
+    let candidates = candidate_networks(repos, &addr_v4)?;
+    let network = find_right_entry(&candidates).ok_or(LookupError::NetworkNotFound)?;
+    let (location, location_source) = find_location(repos, &network)?.ok_or(LookupError::LocationNotFound)?;
+
+    Ok(LookupResult {
+        request_ip: addr,
+        ip: network,
+        location,
+        location_source,
+        dataset_version: repos.dataset_version(),
+        labels: Vec::new(),
+        threats: threat_flags(repos, &addr)?,
+    })
+}
+
+/// GeoLite2 leaves `geoname_id` empty for networks only known at the country level, so fall back
+/// on the registered, then represented, country of the network.
+pub fn find_location(repos: &RepositoryCollection, network: &Ip) -> Result<Option<(Location, LocationSource)>, RepositoryError> {
+    let geoname_ids = [
+        (&network.geoname_id, LocationSource::Geoname),
+        (&network.registered_country_geoname_id, LocationSource::RegisteredCountry),
+        (&network.represented_country_geoname_id, LocationSource::RepresentedCountry),
+    ];
+
+    for (geoname_id, source) in geoname_ids.iter() {
+        if geoname_id.is_empty() {
+            continue;
+        }
+
+        if let Some(location) = repos.location.get(doc! {"geoname_id": geoname_id.as_str()})? {
+            return Ok(Some((location, *source)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Narrows the `ip` collection down to the networks that may contain `addr`, using the
+/// `net`/`sub`/`sub2` octet fields stored alongside each network.
+pub fn candidate_networks(repos: &RepositoryCollection, addr: &Ipv4Addr) -> Result<Vec<Ip>, RepositoryError> {
+    let octets = addr.octets();
+    let net = octets[0] as i32;
+    let sub = octets[1] as i32;
+    let sub2 = octets[2] as i32;
+
+    if repos.ip.count(Some(doc! {"net": net, "sub": sub}))? > 0 {
+        if repos.ip.count(Some(doc! {"net": net, "sub": sub, "sub2": sub2}))? > 0 {
+            return repos.ip.find(doc! {"net": net, "sub": sub, "sub2": sub2});
+        }
+
+        let mut options = FindOptions::new();
+        options.limit = Some(sub2 as i64);
+        options.sort = Some(doc! {"sub": -1});
+        let mut lte_ips_sub = repos.ip.find_with_options(doc! {"net": net, "sub": sub, "sub2": { "$lte": sub2 }}, options)?;
+
+        if let Some(l_sub) = lte_ips_sub.first().map(|ip| ip.sub2) {
+            lte_ips_sub.retain(|ip| ip.sub2 == l_sub);
+        }
+
+        return Ok(lte_ips_sub);
+    }
+
+    let mut options = FindOptions::new();
+    options.limit = Some(sub2 as i64);
+    options.sort = Some(doc! {"sub": -1});
+    let mut lte_ips_sub = repos.ip.find_with_options(doc! {"net": net, "sub": { "$lte": sub }, "sub2": { "$lte": sub2 }}, options)?;
+
+    if let Some(l_sub) = lte_ips_sub.first().map(|ip| ip.sub) {
+        lte_ips_sub.retain(|ip| ip.sub == l_sub);
+    }
+
+    Ok(lte_ips_sub)
+}
+
+pub fn find_right_entry(ips: &Vec<Ip>) -> Option<Ip> {
+    let mut vec_value = get_netmask_array(ips);
+    vec_value.sort_by(|a, b| a.0.cmp(&b.0));
+    vec_value.last().map(|entry| entry.1.clone())
+}
+
+fn get_netmask_array(ips: &Vec<Ip>) -> Vec<(i64, &Ip)> {
+    let mut vec_ips = Vec::new();
+    for ip in ips {
+        match get_netmask_value(ip.netmask.as_str()) {
+            Some(val) => vec_ips.push((val, ip)),
+            None => warn!("Skipping network {} whose netmask {:?} is malformed", ip.network, ip.netmask),
+        }
+    }
+
+    vec_ips
+}
+
+/// Score ranking the candidate networks, the highest one is selected. `None` when `netmask` is not `<octet>/<prefix>`.
+pub fn get_netmask_value(netmask: &str) -> Option<i64> {
+    let mut cyrille = netmask.split('/');
+    let octet = cyrille.next()?.parse::<i64>().ok()?;
+    let prefix = cyrille.next()?.parse::<i64>().ok()?;
+
+    if cyrille.next().is_some() {
+        return None;
+    }
+
+    Some((octet * 8) + (prefix * 256))
+}
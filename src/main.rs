@@ -19,19 +19,46 @@ extern crate env_logger;
 #[macro_use]
 extern crate clap;
 extern crate serde_yaml;
+extern crate lru;
+extern crate csv;
+extern crate ipnet;
+extern crate chrono;
+extern crate chrono_tz;
+extern crate dns_lookup;
 
+mod auth;
+mod cache;
+mod commands;
+mod confidence;
 mod controllers;
+mod countries;
+mod geo;
+mod lookup;
 mod mongo_connection;
 mod models;
+mod networks;
+mod overrides;
+mod rate_limit;
+mod reserved;
+mod resolver;
 mod settings;
+mod threats;
+mod time_zone;
 
 use env_logger::Builder;
 use log::LevelFilter;
 use std::env;
+use std::process;
+use std::time::Duration;
 use saphir::*;
-use self::controllers::LookupController;
+use self::auth::ApiKeyAuthenticator;
+use self::controllers::{AdminController, LookupController};
+use self::lookup::Locator;
 use self::mongo_connection::MongoConnection;
+use self::overrides::OverrideTable;
 use self::models::RepositoryCollection;
+use self::rate_limit::RateLimiter;
+use self::settings::{Command, Settings};
 
 fn main() {
 
@@ -57,14 +84,56 @@ fn main() {
     println!("Loading repositories..");
     repos.load_repositories().expect("Lucid cannot start without fully initializing its repos");
 
+    match config.command {
+        Command::Serve => serve(&config, repos),
+        Command::Dataset(ref command) => exit_on_error(commands::dataset::run(&repos, command)),
+        Command::Import(ref command) => exit_on_error(commands::import::run(&repos, command)),
+        Command::Enrich(ref command) => exit_on_error(commands::enrich::run(&locator(&config, repos), command)),
+        Command::Lookup(ref ip) => exit_on_error(commands::lookup::run(&locator(&config, repos), ip)),
+        Command::Threats(ref command) => exit_on_error(commands::threats::run(&repos, command)),
+        Command::Verify => exit_on_error(commands::verify::run(&repos)),
+    }
+}
+
+fn exit_on_error(result: Result<(), String>) {
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// The lookup stack shared by the server and the commands answering lookups.
+fn locator(config: &Settings, repos: RepositoryCollection) -> Locator {
+    let overrides = OverrideTable::load(repos.clone(), &config.overrides).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1)
+    });
+    let resolver = resolver::from_settings(&config.resolver).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1)
+    });
+
+    Locator::new(repos, overrides, resolver, &config.resolver, &config.cache)
+}
+
+fn serve(config: &Settings, repos: RepositoryCollection) {
+    let auth = ApiKeyAuthenticator::new(config.auth.clone(), repos.clone());
+    let limiter = RateLimiter::new(config.rate_limit.clone()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1)
+    });
+    let locator = locator(config, repos);
+    locator.watch_dataset(Duration::from_secs(config.dataset.poll_interval_secs.max(1)));
+
     let server_builder = Server::builder().configure_router(|router| {
-        let lookup = LookupController::new(repos.clone());
-        router.add(lookup)
+        // Registered first, the lookup controller matches every path
+        let admin = AdminController::new(locator.clone(), auth.clone(), limiter.clone());
+        let lookup = LookupController::new(locator.clone(), auth.clone(), limiter.clone());
+        router.add(admin).add(lookup)
     }).configure_listener(|list_config| {
         list_config.set_uri("http://0.0.0.0:7974")
     }).build();
 
     println!("Server listening on port 7974..");
     let _ = server_builder.run();
-}
-
+}
\ No newline at end of file
@@ -0,0 +1,168 @@
+use crate::models::{Repository, RepositoryError};
+use crate::mongo_connection::MongoConnection;
+use mongodb::db::ThreadedDatabase;
+use mongodb::coll::Collection;
+use mongodb::coll::options::{FindOneAndUpdateOptions, ReturnDocument};
+use bson::{Bson, Document};
+use bson::oid::ObjectId;
+
+fn default_bson_id() -> ObjectId {
+    ObjectId::new().unwrap()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiKey {
+    #[serde(rename = "_id")]
+    #[serde(default = "default_bson_id")]
+    pub id: ObjectId,
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub daily_quota: i64,
+    #[serde(default)]
+    pub disabled: bool,
+    /// Grants access to the `/admin` endpoints
+    #[serde(default)]
+    pub admin: bool,
+}
+
+impl ApiKey {
+    pub fn new() -> Self{
+        ApiKey{
+            id: ObjectId::new().unwrap(),
+            key: String::new(),
+            name: String::new(),
+            daily_quota: 0,
+            disabled: false,
+            admin: false
+        }
+    }
+}
+
+pub struct ApiKeyRepository {
+    db_instance: Option<MongoConnection>,
+}
+
+impl Default for ApiKeyRepository {
+    fn default() -> Self {
+        ApiKeyRepository {
+            db_instance: None,
+        }
+    }
+}
+
+impl Clone for ApiKeyRepository {
+    fn clone(&self) -> Self {
+        if let Some(ref db) = self.db_instance {
+            ApiKeyRepository {
+                db_instance: Some(db.clone()),
+            }
+        } else {
+            ApiKeyRepository {
+                db_instance: None,
+            }
+        }
+    }
+}
+
+impl Repository for ApiKeyRepository {
+    type Model = ApiKey;
+
+    fn init(&mut self, db_instance: MongoConnection) -> Result<(), RepositoryError> {
+        self.db_instance = Some(db_instance);
+        Ok(())
+    }
+
+    /// Two documents with the same key could grant different quotas
+    fn unique_indexes(&self) -> Vec<Document> {
+        vec![doc! {"key": 1}]
+    }
+
+    fn get_collection(&self) -> Result<Collection, RepositoryError> {
+        if let Some(ref db) = self.db_instance {
+            Ok(db.get()?.collection("api_keys"))
+        } else {
+            Err(RepositoryError::UninitializedRepoError)
+        }
+    }
+}
+/// Requests made with an API key during one UTC day, shared by every server instance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiKeyUsage {
+    #[serde(rename = "_id")]
+    #[serde(default = "default_bson_id")]
+    pub id: ObjectId,
+    pub key: String,
+    /// Days since the Unix epoch
+    pub day: i64,
+    pub count: i64,
+}
+
+pub struct ApiKeyUsageRepository {
+    db_instance: Option<MongoConnection>,
+}
+
+impl Default for ApiKeyUsageRepository {
+    fn default() -> Self {
+        ApiKeyUsageRepository {
+            db_instance: None,
+        }
+    }
+}
+
+impl Clone for ApiKeyUsageRepository {
+    fn clone(&self) -> Self {
+        if let Some(ref db) = self.db_instance {
+            ApiKeyUsageRepository {
+                db_instance: Some(db.clone()),
+            }
+        } else {
+            ApiKeyUsageRepository {
+                db_instance: None,
+            }
+        }
+    }
+}
+
+impl ApiKeyUsageRepository {
+    /// Counts one more request made with `key` on `day` and returns the count of the day, this request included.
+    pub fn increment(&self, key: &str, day: i64) -> Result<i64, RepositoryError> {
+        let mut options = FindOneAndUpdateOptions::new();
+        options.upsert = Some(true);
+        options.return_document = Some(ReturnDocument::After);
+
+        let usage = self.get_collection()?.find_one_and_update(
+            doc! {"key": key, "day": day},
+            doc! {"$inc": { "count": 1 }},
+            Some(options),
+        )?;
+
+        match usage.as_ref().map(|usage| usage.get("count")) {
+            Some(Some(&Bson::I32(count))) => Ok(count as i64),
+            Some(Some(&Bson::I64(count))) => Ok(count),
+            _ => Err(RepositoryError::UpdateError),
+        }
+    }
+}
+
+impl Repository for ApiKeyUsageRepository {
+    type Model = ApiKeyUsage;
+
+    fn init(&mut self, db_instance: MongoConnection) -> Result<(), RepositoryError> {
+        self.db_instance = Some(db_instance);
+        Ok(())
+    }
+
+    /// Concurrent first requests of the day would otherwise upsert one document each
+    fn unique_indexes(&self) -> Vec<Document> {
+        vec![doc! {"key": 1, "day": 1}]
+    }
+
+    fn get_collection(&self) -> Result<Collection, RepositoryError> {
+        if let Some(ref db) = self.db_instance {
+            Ok(db.get()?.collection("api_key_usage"))
+        } else {
+            Err(RepositoryError::UninitializedRepoError)
+        }
+    }
+}
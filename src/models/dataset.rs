@@ -0,0 +1,171 @@
+use crate::models::{Repository, RepositoryError};
+use crate::mongo_connection::MongoConnection;
+use mongodb::db::ThreadedDatabase;
+use mongodb::coll::Collection;
+
+pub const ACTIVE_DATASET_ID: &'static str = "active_dataset";
+
+/// Generation of the threat ranges imported before generations existed
+pub const LEGACY_THREAT_GENERATION: &'static str = "legacy";
+
+/// Generation of a threat list that lookups match, switched by `spotme threats import` once the new ranges are written.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThreatListGeneration {
+    pub list: String,
+    pub generation: String,
+}
+
+/// Pointer to the dataset version the `ip` and `location` repositories read from.
+/// An empty version designates the legacy, unversioned `ip` and `location` collections.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActiveDataset {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub version: String,
+    /// Previously active versions, most recent last
+    #[serde(default)]
+    pub history: Vec<String>,
+    /// Unix timestamp of the last switch
+    #[serde(default)]
+    pub switched_at: i64,
+    /// Incremented by each threat list import, cached lookups are dropped when it changes
+    #[serde(default)]
+    pub threats_revision: i64,
+    #[serde(default)]
+    pub threat_lists: Vec<ThreatListGeneration>,
+    /// Incremented by each `/admin` write to the `ip` and `location` collections, for the same reason
+    #[serde(default)]
+    pub data_revision: i64,
+}
+
+impl ActiveDataset {
+    pub fn new() -> Self{
+        ActiveDataset {
+            id: ACTIVE_DATASET_ID.to_string(),
+            version: String::new(),
+            history: Vec::new(),
+            switched_at: 0,
+            threats_revision: 0,
+            threat_lists: Vec::new(),
+            data_revision: 0
+        }
+    }
+}
+
+pub fn dataset_info_id(version: &str) -> String {
+    format!("dataset_{}", version)
+}
+
+/// Description of an imported dataset version, written by `spotme import`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DatasetInfo {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub version: String,
+    pub source: String,
+    pub build_date: String,
+    pub ip_count: i64,
+    pub location_count: i64,
+    /// Unix timestamp of the end of the import
+    pub imported_at: i64,
+}
+
+impl DatasetInfo {
+    pub fn new(version: &str) -> Self{
+        DatasetInfo {
+            id: dataset_info_id(version),
+            version: version.to_string(),
+            source: String::new(),
+            build_date: String::new(),
+            ip_count: 0,
+            location_count: 0,
+            imported_at: 0
+        }
+    }
+}
+
+pub struct MetadataRepository {
+    db_instance: Option<MongoConnection>,
+}
+
+impl Default for MetadataRepository {
+    fn default() -> Self {
+        MetadataRepository {
+            db_instance: None,
+        }
+    }
+}
+
+impl Clone for MetadataRepository {
+    fn clone(&self) -> Self {
+        if let Some(ref db) = self.db_instance {
+            MetadataRepository {
+                db_instance: Some(db.clone()),
+            }
+        } else {
+            MetadataRepository {
+                db_instance: None,
+            }
+        }
+    }
+}
+
+impl Repository for MetadataRepository {
+    type Model = ActiveDataset;
+
+    fn init(&mut self, db_instance: MongoConnection) -> Result<(), RepositoryError> {
+        self.db_instance = Some(db_instance);
+        Ok(())
+    }
+
+    fn get_collection(&self) -> Result<Collection, RepositoryError> {
+        if let Some(ref db) = self.db_instance {
+            Ok(db.get()?.collection("metadata"))
+        } else {
+            Err(RepositoryError::UninitializedRepoError)
+        }
+    }
+}
+
+pub struct DatasetInfoRepository {
+    db_instance: Option<MongoConnection>,
+}
+
+impl Default for DatasetInfoRepository {
+    fn default() -> Self {
+        DatasetInfoRepository {
+            db_instance: None,
+        }
+    }
+}
+
+impl Clone for DatasetInfoRepository {
+    fn clone(&self) -> Self {
+        if let Some(ref db) = self.db_instance {
+            DatasetInfoRepository {
+                db_instance: Some(db.clone()),
+            }
+        } else {
+            DatasetInfoRepository {
+                db_instance: None,
+            }
+        }
+    }
+}
+
+impl Repository for DatasetInfoRepository {
+    type Model = DatasetInfo;
+
+    fn init(&mut self, db_instance: MongoConnection) -> Result<(), RepositoryError> {
+        self.db_instance = Some(db_instance);
+        Ok(())
+    }
+
+    fn get_collection(&self) -> Result<Collection, RepositoryError> {
+        if let Some(ref db) = self.db_instance {
+            Ok(db.get()?.collection("metadata"))
+        } else {
+            Err(RepositoryError::UninitializedRepoError)
+        }
+    }
+}
@@ -1,13 +1,32 @@
-use crate::models::{Repository, RepositoryError};
+use crate::models::{DatasetVersion, Repository, RepositoryError};
 use crate::mongo_connection::MongoConnection;
 use mongodb::db::ThreadedDatabase;
 use mongodb::coll::Collection;
+use bson::Document;
 use bson::oid::ObjectId;
 
 fn default_bson_id() -> ObjectId {
     ObjectId::new().unwrap()
 }
 
+/// GeoJSON point, as required by `2dsphere` indexes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GeoPoint {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Longitude first, then latitude
+    pub coordinates: Vec<f64>,
+}
+
+impl GeoPoint {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        GeoPoint {
+            kind: "Point".to_string(),
+            coordinates: vec![longitude, latitude],
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Ip {
     #[serde(rename = "_id")]
@@ -15,13 +34,25 @@ pub struct Ip {
     pub id: ObjectId,
     pub network: String,
     pub geoname_id: String,
+    #[serde(default)]
+    pub registered_country_geoname_id: String,
+    #[serde(default)]
+    pub represented_country_geoname_id: String,
     pub latitude: String,
     pub longitude: String,
     pub accuracy_radius: String,
     pub net: i32,
     pub sub: i32,
     pub sub2: i32,
-    pub netmask: String
+    pub netmask: String,
+    /// Location of the network, set by `spotme import`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub centroid: Option<GeoPoint>,
+    /// First and last addresses of the network as integers, set by `spotme import`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_start: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_end: Option<i64>,
 }
 
 impl Ip {
@@ -30,38 +61,53 @@ impl Ip {
             id: ObjectId::new().unwrap(),
             network: String::new(),
             geoname_id: String::new(),
+            registered_country_geoname_id: String::new(),
+            represented_country_geoname_id: String::new(),
             latitude: String::new(),
             longitude: String::new(),
             accuracy_radius: String::new(),
             net: 0,
             sub: 0,
             sub2: 0,
-            netmask: String::new()
+            netmask: String::new(),
+            centroid: None,
+            range_start: None,
+            range_end: None
         }
     }
 }
 
 pub struct IpRepository {
     db_instance: Option<MongoConnection>,
+    dataset: DatasetVersion,
 }
 
-impl Default for IpRepository {
-    fn default() -> Self {
+impl IpRepository {
+    pub fn new(dataset: DatasetVersion) -> Self {
         IpRepository {
             db_instance: None,
+            dataset,
         }
     }
 }
 
+impl Default for IpRepository {
+    fn default() -> Self {
+        IpRepository::new(DatasetVersion::default())
+    }
+}
+
 impl Clone for IpRepository {
     fn clone(&self) -> Self {
         if let Some(ref db) = self.db_instance {
             IpRepository {
                 db_instance: Some(db.clone()),
+                dataset: self.dataset.clone(),
             }
         } else {
             IpRepository {
                 db_instance: None,
+                dataset: self.dataset.clone(),
             }
         }
     }
@@ -75,9 +121,19 @@ impl Repository for IpRepository {
         Ok(())
     }
 
+    fn indexes(&self) -> Vec<Document> {
+        vec![
+            doc! {"net": 1, "sub": 1, "sub2": 1},
+            doc! {"geoname_id": 1},
+            doc! {"registered_country_geoname_id": 1},
+            doc! {"centroid": "2dsphere"},
+            doc! {"range_start": 1, "range_end": 1},
+        ]
+    }
+
     fn get_collection(&self) -> Result<Collection, RepositoryError> {
         if let Some(ref db) = self.db_instance {
-            Ok(db.get()?.collection("ip"))
+            Ok(db.get()?.collection(&self.dataset.collection_name("ip")))
         } else {
             Err(RepositoryError::UninitializedRepoError)
         }
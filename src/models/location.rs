@@ -1,7 +1,8 @@
-use crate::models::{Repository, RepositoryError};
+use crate::models::{DatasetVersion, Repository, RepositoryError};
 use crate::mongo_connection::MongoConnection;
 use mongodb::db::ThreadedDatabase;
 use mongodb::coll::Collection;
+use bson::Document;
 use bson::oid::ObjectId;
 
 fn default_bson_id() -> ObjectId {
@@ -15,6 +16,8 @@ pub struct Location {
     pub id: ObjectId,
     pub geoname_id: String,
     pub continent_name: String,
+    #[serde(default)]
+    pub country_iso_code: String,
     pub country_name: String,
     pub subdivision_1_name: String,
     pub subdivision_2_name: String,
@@ -28,6 +31,7 @@ impl Location {
             id: ObjectId::new().unwrap(),
             geoname_id: String::new(),
             continent_name: String::new(),
+            country_iso_code: String::new(),
             country_name: String::new(),
             subdivision_1_name: String::new(),
             subdivision_2_name: String::new(),
@@ -39,25 +43,35 @@ impl Location {
 
 pub struct LocationRepository {
     db_instance: Option<MongoConnection>,
+    dataset: DatasetVersion,
 }
 
-impl Default for LocationRepository {
-    fn default() -> Self {
+impl LocationRepository {
+    pub fn new(dataset: DatasetVersion) -> Self {
         LocationRepository {
             db_instance: None,
+            dataset,
         }
     }
 }
 
+impl Default for LocationRepository {
+    fn default() -> Self {
+        LocationRepository::new(DatasetVersion::default())
+    }
+}
+
 impl Clone for LocationRepository {
     fn clone(&self) -> Self {
         if let Some(ref db) = self.db_instance {
             LocationRepository {
                 db_instance: Some(db.clone()),
+                dataset: self.dataset.clone(),
             }
         } else {
             LocationRepository {
                 db_instance: None,
+                dataset: self.dataset.clone(),
             }
         }
     }
@@ -71,9 +85,17 @@ impl Repository for LocationRepository {
         Ok(())
     }
 
+    fn indexes(&self) -> Vec<Document> {
+        vec![
+            doc! {"geoname_id": 1},
+            doc! {"country_iso_code": 1},
+            doc! {"city_name": 1},
+        ]
+    }
+
     fn get_collection(&self) -> Result<Collection, RepositoryError> {
         if let Some(ref db) = self.db_instance {
-            Ok(db.get()?.collection("location"))
+            Ok(db.get()?.collection(&self.dataset.collection_name("location")))
         } else {
             Err(RepositoryError::UninitializedRepoError)
         }
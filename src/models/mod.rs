@@ -1,5 +1,9 @@
 pub mod location;
 pub mod ip;
+pub mod api_key;
+pub mod dataset;
+pub mod network_override;
+pub mod threat;
 
 use bson::Bson;
 use bson::Document;
@@ -9,6 +13,13 @@ use bson::oid::ObjectId;
 use mongodb::coll::options::AggregateOptions;
 use mongodb::coll::results::UpdateResult;
 use mongodb::coll::options::FindOptions;
+use mongodb::coll::options::IndexOptions;
+use mongodb::coll::options::ReplaceOptions;
+use mongodb::coll::options::UpdateOptions;
+use mongodb::db::ThreadedDatabase;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use self::dataset::{ActiveDataset, ThreatListGeneration, ACTIVE_DATASET_ID, LEGACY_THREAT_GENERATION};
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -46,27 +57,282 @@ impl From<::mongodb::Error> for RepositoryError {
     }
 }
 
+/// Dataset version shared by the repositories of versioned collections, so switching it
+/// moves all of them to the new collections at once.
+#[derive(Clone, Default)]
+pub struct DatasetVersion(Arc<RwLock<Option<String>>>);
+
+impl DatasetVersion {
+    pub fn get(&self) -> Option<String> {
+        self.0.read().expect("Dataset version lock poisoned").clone()
+    }
+
+    pub fn set(&self, version: Option<String>) {
+        *self.0.write().expect("Dataset version lock poisoned") = version;
+    }
+
+    pub fn collection_name(&self, base: &str) -> String {
+        match self.get() {
+            Some(version) => versioned_collection_name(base, &version),
+            None => base.to_string(),
+        }
+    }
+}
+
+pub fn versioned_collection_name(base: &str, version: &str) -> String {
+    if version.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}_{}", base, version)
+    }
+}
+
 #[derive(Clone)]
 pub struct RepositoryCollection {
     db_instance: crate::mongo_connection::MongoConnection,
+    dataset: DatasetVersion,
     pub ip: ip::IpRepository,
     pub location: location::LocationRepository,
+    pub api_key: api_key::ApiKeyRepository,
+    pub api_key_usage: api_key::ApiKeyUsageRepository,
+    pub overrides: network_override::NetworkOverrideRepository,
+    pub threats: threat::ThreatRangeRepository,
+    pub metadata: dataset::MetadataRepository,
+    pub dataset_info: dataset::DatasetInfoRepository,
 }
 
 impl RepositoryCollection {
     pub fn new(db: crate::mongo_connection::MongoConnection) -> Self {
+        let dataset = DatasetVersion::default();
+
         RepositoryCollection {
             db_instance: db,
-            ip: Default::default(),
-            location: Default::default()
+            ip: ip::IpRepository::new(dataset.clone()),
+            location: location::LocationRepository::new(dataset.clone()),
+            dataset,
+            api_key: Default::default(),
+            api_key_usage: Default::default(),
+            overrides: Default::default(),
+            threats: Default::default(),
+            metadata: Default::default(),
+            dataset_info: Default::default()
         }
     }
 
     pub fn load_repositories(&mut self) -> Result<(), RepositoryError>{
+        self.init_repositories()?;
+        self.refresh_dataset()?;
+        self.ensure_indexes()?;
+        self.adopt_legacy_threat_lists()?;
+        Ok(())
+    }
+
+    pub fn ensure_indexes(&self) -> Result<(), RepositoryError> {
+        self.ip.ensure_indexes()?;
+        self.location.ensure_indexes()?;
+        self.api_key.ensure_indexes()?;
+        self.api_key_usage.ensure_indexes()?;
+        self.overrides.ensure_indexes()?;
+        self.threats.ensure_indexes()?;
+        Ok(())
+    }
+
+    fn init_repositories(&mut self) -> Result<(), RepositoryError>{
         self.ip.init(self.db_instance.clone())?;
         self.location.init(self.db_instance.clone())?;
+        self.api_key.init(self.db_instance.clone())?;
+        self.api_key_usage.init(self.db_instance.clone())?;
+        self.overrides.init(self.db_instance.clone())?;
+        self.threats.init(self.db_instance.clone())?;
+        self.metadata.init(self.db_instance.clone())?;
+        self.dataset_info.init(self.db_instance.clone())?;
+        Ok(())
+    }
+
+    /// Repositories reading from and writing to the collections of `version`, whatever version is active.
+    pub fn pinned_to(&self, version: &str) -> Result<RepositoryCollection, RepositoryError> {
+        let mut repos = RepositoryCollection::new(self.db_instance.clone());
+        repos.init_repositories()?;
+        repos.dataset.set(Some(version.to_string()));
+        Ok(repos)
+    }
+
+    /// Pointer to the active dataset, `None` when no version was ever activated.
+    pub fn active_dataset(&self) -> Result<Option<ActiveDataset>, RepositoryError> {
+        self.metadata.get(doc! {"_id": ACTIVE_DATASET_ID})
+    }
+
+    pub fn dataset_info(&self, version: &str) -> Result<Option<dataset::DatasetInfo>, RepositoryError> {
+        self.dataset_info.get(doc! {"_id": dataset::dataset_info_id(version)})
+    }
+
+    /// Version currently served, `None` for the legacy unversioned collections.
+    pub fn dataset_version(&self) -> Option<String> {
+        self.dataset.get()
+    }
+
+    /// Points the repositories to the version recorded in the `metadata` collection.
+    /// Returns whether the served version changed.
+    pub fn refresh_dataset(&self) -> Result<bool, RepositoryError> {
+        let version = self.active_dataset()?
+            .map(|active| active.version)
+            .filter(|version| !version.is_empty());
+
+        if version != self.dataset.get() {
+            // Never serve a version whose indexes could not be built
+            self.pinned_to(version.as_ref().map(String::as_str).unwrap_or(""))?.ensure_indexes()?;
+            self.dataset.set(version);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Revision of the threat lists, bumped by every `spotme threats import`.
+    pub fn threats_revision(&self) -> Result<i64, RepositoryError> {
+        Ok(self.active_dataset()?.map_or(0, |active| active.threats_revision))
+    }
+
+    /// Generations of the threat lists that lookups match, one per list.
+    pub fn threat_lists(&self) -> Result<Vec<ThreatListGeneration>, RepositoryError> {
+        Ok(self.active_dataset()?.map_or_else(Vec::new, |active| active.threat_lists))
+    }
+
+    /// Atomically makes `generation` the one matched for `list`, and tells the running servers
+    /// that the threat lists changed so that they drop the cached flags.
+    pub fn switch_threat_list(&self, list: &str, generation: &str) -> Result<(), RepositoryError> {
+        let collection = self.metadata.get_collection()?;
+
+        let switched = collection.update_one(
+            doc! {"_id": ACTIVE_DATASET_ID, "threat_lists.list": list},
+            doc! {"$set": { "threat_lists.$.generation": generation }, "$inc": { "threats_revision": 1 }},
+            None,
+        )?;
+
+        // First import of the list
+        if switched.matched_count == 0 {
+            let mut options = UpdateOptions::new();
+            options.upsert = Some(true);
+
+            collection.update_one(
+                doc! {"_id": ACTIVE_DATASET_ID, "threat_lists.list": { "$ne": list }},
+                doc! {
+                    "$push": { "threat_lists": { "list": list, "generation": generation } },
+                    "$inc": { "threats_revision": 1 },
+                    "$setOnInsert": { "version": "" }
+                },
+                Some(options),
+            )?;
+        }
+
         Ok(())
     }
+
+    /// Ranges imported before generations existed become the active generation of their list.
+    /// Importing a list again deletes them, so they only remain for lists never imported since.
+    fn adopt_legacy_threat_lists(&self) -> Result<(), RepositoryError> {
+        let legacy_filter = doc! {"generation": { "$exists": false }};
+
+        if self.threats.count(Some(legacy_filter.clone()))? == 0 {
+            return Ok(());
+        }
+
+        let lists = self.threats.get_collection()?.distinct("list", Some(legacy_filter), None)?;
+
+        for list in lists.iter().filter_map(Bson::as_str) {
+            self.threats.update_many(
+                doc! {"list": list, "generation": { "$exists": false }},
+                doc! {"$set": { "generation": LEGACY_THREAT_GENERATION }},
+            )?;
+            self.switch_threat_list(list, LEGACY_THREAT_GENERATION)?;
+        }
+
+        Ok(())
+    }
+
+    /// Revision of the networks and locations of the active dataset, bumped by every `/admin` write to them.
+    pub fn data_revision(&self) -> Result<i64, RepositoryError> {
+        Ok(self.active_dataset()?.map_or(0, |active| active.data_revision))
+    }
+
+    /// Tells the running servers that a network or location was edited, so that they drop the cached lookups.
+    pub fn bump_data_revision(&self) -> Result<(), RepositoryError> {
+        self.bump_revision("data_revision")
+    }
+
+    fn bump_revision(&self, field: &str) -> Result<(), RepositoryError> {
+        let mut options = UpdateOptions::new();
+        options.upsert = Some(true);
+
+        self.metadata.get_collection()?.update_one(
+            doc! {"_id": ACTIVE_DATASET_ID},
+            doc! {"$inc": { field: 1 }, "$setOnInsert": { "version": "" }},
+            Some(options),
+        )?;
+
+        Ok(())
+    }
+
+    /// Versions that have both an `ip_<version>` and a `location_<version>` collection.
+    pub fn list_datasets(&self) -> Result<Vec<String>, RepositoryError> {
+        let names = self.db_instance.get()?.collection_names(None)?;
+
+        let mut versions = names.iter()
+            .filter(|name| name.starts_with("ip_"))
+            .map(|name| name["ip_".len()..].to_string())
+            .filter(|version| names.contains(&versioned_collection_name("location", version)))
+            .collect::<Vec<String>>();
+        versions.sort();
+
+        Ok(versions)
+    }
+
+    /// Atomically records `version` as the active dataset. The previous one is kept for `rollback_dataset`.
+    pub fn activate_dataset(&self, version: &str) -> Result<(), RepositoryError> {
+        let db = self.db_instance.get()?;
+
+        for base in &["ip", "location"] {
+            let name = versioned_collection_name(base, version);
+            if db.collection(&name).count(None, None)? == 0 {
+                return Err(RepositoryError::Other(format!("Collection {} is missing or empty", name)));
+            }
+        }
+
+        self.pinned_to(version)?.ensure_indexes()?;
+
+        let mut active = self.active_dataset()?.unwrap_or_else(ActiveDataset::new);
+
+        if active.version == version {
+            return Err(RepositoryError::Other(format!("Dataset {} is already active", version)));
+        }
+
+        let previous = active.version.clone();
+        active.history.push(previous);
+        active.version = version.to_string();
+        active.switched_at = unix_timestamp();
+
+        self.metadata.upsert(doc! {"_id": ACTIVE_DATASET_ID}, active)?;
+        Ok(())
+    }
+
+    /// Switches back to the previously active dataset and returns its version.
+    pub fn rollback_dataset(&self) -> Result<String, RepositoryError> {
+        let mut active = self.active_dataset()?
+            .ok_or_else(|| RepositoryError::Other("No dataset was ever activated".to_string()))?;
+
+        let previous = active.history.pop()
+            .ok_or_else(|| RepositoryError::Other("There is no previous dataset to roll back to".to_string()))?;
+
+        active.version = previous.clone();
+        active.switched_at = unix_timestamp();
+
+        self.metadata.upsert(doc! {"_id": ACTIVE_DATASET_ID}, active)?;
+        Ok(previous)
+    }
+}
+
+pub fn unix_timestamp() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
 }
 
 pub mod oid{
@@ -99,6 +365,52 @@ pub trait Repository {
     fn init(&mut self, db_instance: crate::mongo_connection::MongoConnection) -> Result<(), RepositoryError>;
     fn get_collection(&self) -> Result<::mongodb::coll::Collection, RepositoryError>;
 
+    /// Keys of the indexes the queries on this repository rely on
+    fn indexes(&self) -> Vec<Document> {
+        Vec::new()
+    }
+
+    /// Keys of the indexes that must also reject duplicates
+    fn unique_indexes(&self) -> Vec<Document> {
+        Vec::new()
+    }
+
+    /// Creates the indexes declared by `indexes` and `unique_indexes`, creating an index that already exists is a no-op.
+    /// A non-unique index on keys declared unique since is replaced, which fails as long as duplicates remain.
+    fn ensure_indexes(&self) -> Result<(), RepositoryError> {
+        let collection = self.get_collection()?;
+
+        for keys in self.indexes() {
+            collection.create_index(keys, None)?;
+        }
+
+        let unique_indexes = self.unique_indexes();
+        if unique_indexes.is_empty() {
+            return Ok(());
+        }
+
+        for index in collection.list_indexes()? {
+            let index = index?;
+
+            let replaced = match index.get_document("key") {
+                Ok(keys) => unique_indexes.contains(keys) && !index.get_bool("unique").unwrap_or(false),
+                Err(_) => false,
+            };
+
+            if replaced {
+                collection.drop_index_string(index.get_str("name").unwrap_or_default().to_string())?;
+            }
+        }
+
+        for keys in unique_indexes {
+            let mut options = IndexOptions::new();
+            options.unique = Some(true);
+            collection.create_index(keys, Some(options))?;
+        }
+
+        Ok(())
+    }
+
     fn insert(&self, model: <Self as Repository>::Model) -> Result<Option<Bson>, RepositoryError> where <Self as Repository>::Model: ::serde::Serialize {
         let serialized_model = to_bson(&model)?;
 
@@ -129,6 +441,35 @@ pub trait Repository {
         InsertManyResult::new(None, None)
     }
 
+    /// Inserts `models` and returns how many documents were written. Unlike `insert_many`, failures are reported.
+    fn insert_all(&self, models: Vec<<Self as Repository>::Model>) -> Result<i64, RepositoryError> where <Self as Repository>::Model: ::serde::Serialize {
+        let mut documents = Vec::with_capacity(models.len());
+        for model in models {
+            match to_bson(&model)? {
+                Bson::Document(document) => documents.push(document),
+                _ => return Err(RepositoryError::InsertError),
+            }
+        }
+
+        if documents.is_empty() {
+            return Ok(0);
+        }
+
+        let result = self.get_collection()?.insert_many(documents, None)?;
+
+        if let Some(e) = result.bulk_write_exception {
+            return Err(RepositoryError::Other(format!("{:?}", e)));
+        }
+
+        Ok(result.inserted_ids.map_or(0, |ids| ids.len() as i64))
+    }
+
+    /// Applies the `update` operators to every document matching `doc`, returns the number of modified documents.
+    fn update_many(&self, doc: Document, update: Document) -> Result<i64, RepositoryError> {
+        let result = self.get_collection()?.update_many(doc, update, None)?;
+        Ok(result.modified_count as i64)
+    }
+
     fn update(&self, doc: Document, model: <Self as Repository>::Model) -> Result<UpdateResult, RepositoryError> where <Self as Repository>::Model: ::serde::Serialize {
         let serialized_model = to_bson(&model)?;
 
@@ -141,6 +482,20 @@ pub trait Repository {
         }
     }
 
+    fn upsert(&self, doc: Document, model: <Self as Repository>::Model) -> Result<UpdateResult, RepositoryError> where <Self as Repository>::Model: ::serde::Serialize {
+        let serialized_model = to_bson(&model)?;
+
+        if let Bson::Document(mut document) = serialized_model {
+            let _res = document.remove("_id");
+            let mut options = ReplaceOptions::new();
+            options.upsert = Some(true);
+            let result = self.get_collection()?.replace_one(doc, document, Some(options))?;
+            Ok(result)
+        } else {
+            Err(RepositoryError::UpdateError)
+        }
+    }
+
     fn update_by_id(&self, bson_id: ObjectId, model: <Self as Repository>::Model) -> Result<UpdateResult, RepositoryError> where <Self as Repository>::Model: ::serde::Serialize {
         self.update(doc! { "_id": bson_id }, model)
     }
@@ -150,6 +505,11 @@ pub trait Repository {
         Ok(())
     }
 
+    fn delete_many(&self, doc: Document) -> Result<i64, RepositoryError> {
+        let result = self.get_collection()?.delete_many(doc, None)?;
+        Ok(result.deleted_count as i64)
+    }
+
     fn delete_by_id(&self, bson_id: ObjectId) -> Result<(), RepositoryError> {
         self.delete(doc! { "_id": bson_id })
     }
@@ -188,6 +548,18 @@ pub trait Repository {
         Ok(model_vec)
     }
 
+    /// Streams every model matching `doc` to `f`, including the documents that could not be decoded
+    fn scan<F>(&self, doc: Option<Document>, mut f: F) -> Result<(), RepositoryError> where <Self as Repository>::Model: ::serde::Deserialize<'static>, F: FnMut(Result<<Self as Repository>::Model, ::bson::DecoderError>) {
+        let documents_cursor = self.get_collection()?.find(doc, None)?;
+
+        for doc_res in documents_cursor {
+            let model_document = doc_res?;
+            f(from_bson(Bson::Document(model_document)));
+        }
+
+        Ok(())
+    }
+
     fn find(&self, doc: Document) -> Result<Vec<<Self as Repository>::Model>, RepositoryError> where <Self as Repository>::Model: ::serde::Deserialize<'static> {
         let mut model_vec = Vec::new();
         let documents_cursor = self.get_collection()?.find(Some(doc), None)?;
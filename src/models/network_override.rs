@@ -0,0 +1,93 @@
+use crate::models::{Repository, RepositoryError};
+use crate::mongo_connection::MongoConnection;
+use mongodb::db::ThreadedDatabase;
+use mongodb::coll::Collection;
+use bson::Document;
+use bson::oid::ObjectId;
+
+fn default_bson_id() -> ObjectId {
+    ObjectId::new().unwrap()
+}
+
+/// Custom location of a network, winning over the `ip` collection.
+/// Stored in the `overrides` collection or listed in the file referenced by `Settings`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetworkOverride {
+    #[serde(rename = "_id")]
+    #[serde(default = "default_bson_id")]
+    pub id: ObjectId,
+    /// IPv4 or IPv6 CIDR
+    pub network: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub geoname_id: String,
+    #[serde(default)]
+    pub continent_name: String,
+    #[serde(default)]
+    pub country_iso_code: String,
+    #[serde(default)]
+    pub country_name: String,
+    #[serde(default)]
+    pub subdivision_1_name: String,
+    #[serde(default)]
+    pub subdivision_2_name: String,
+    #[serde(default)]
+    pub city_name: String,
+    #[serde(default)]
+    pub time_zone: String,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    /// In kilometers
+    #[serde(default)]
+    pub accuracy_radius: Option<i32>,
+}
+
+pub struct NetworkOverrideRepository {
+    db_instance: Option<MongoConnection>,
+}
+
+impl Default for NetworkOverrideRepository {
+    fn default() -> Self {
+        NetworkOverrideRepository {
+            db_instance: None,
+        }
+    }
+}
+
+impl Clone for NetworkOverrideRepository {
+    fn clone(&self) -> Self {
+        if let Some(ref db) = self.db_instance {
+            NetworkOverrideRepository {
+                db_instance: Some(db.clone()),
+            }
+        } else {
+            NetworkOverrideRepository {
+                db_instance: None,
+            }
+        }
+    }
+}
+
+impl Repository for NetworkOverrideRepository {
+    type Model = NetworkOverride;
+
+    fn init(&mut self, db_instance: MongoConnection) -> Result<(), RepositoryError> {
+        self.db_instance = Some(db_instance);
+        Ok(())
+    }
+
+    fn indexes(&self) -> Vec<Document> {
+        vec![doc! {"network": 1}]
+    }
+
+    fn get_collection(&self) -> Result<Collection, RepositoryError> {
+        if let Some(ref db) = self.db_instance {
+            Ok(db.get()?.collection("overrides"))
+        } else {
+            Err(RepositoryError::UninitializedRepoError)
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::models::{Repository, RepositoryError};
+use crate::mongo_connection::MongoConnection;
+use mongodb::db::ThreadedDatabase;
+use mongodb::coll::Collection;
+use bson::Document;
+use bson::oid::ObjectId;
+
+fn default_bson_id() -> ObjectId {
+    ObjectId::new().unwrap()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreatKind {
+    Tor,
+    AnonymousProxy,
+    Hosting,
+}
+
+/// An IPv4 range of an imported threat list, written by `spotme threats import`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThreatRange {
+    #[serde(rename = "_id")]
+    #[serde(default = "default_bson_id")]
+    pub id: ObjectId,
+    /// Name the list was imported under, e.g. `tor`
+    pub list: String,
+    /// Import the range belongs to, only the generation recorded in `active_dataset` is matched
+    #[serde(default)]
+    pub generation: String,
+    pub kind: ThreatKind,
+    /// First and last addresses of the range, as integers
+    pub start: i64,
+    pub end: i64,
+}
+
+pub struct ThreatRangeRepository {
+    db_instance: Option<MongoConnection>,
+}
+
+impl Default for ThreatRangeRepository {
+    fn default() -> Self {
+        ThreatRangeRepository {
+            db_instance: None,
+        }
+    }
+}
+
+impl Clone for ThreatRangeRepository {
+    fn clone(&self) -> Self {
+        if let Some(ref db) = self.db_instance {
+            ThreatRangeRepository {
+                db_instance: Some(db.clone()),
+            }
+        } else {
+            ThreatRangeRepository {
+                db_instance: None,
+            }
+        }
+    }
+}
+
+impl Repository for ThreatRangeRepository {
+    type Model = ThreatRange;
+
+    fn init(&mut self, db_instance: MongoConnection) -> Result<(), RepositoryError> {
+        self.db_instance = Some(db_instance);
+        Ok(())
+    }
+
+    /// Lookups read the range with the highest `start` of each active generation
+    fn indexes(&self) -> Vec<Document> {
+        vec![doc! {"list": 1, "generation": 1, "start": 1}]
+    }
+
+    fn get_collection(&self) -> Result<Collection, RepositoryError> {
+        if let Some(ref db) = self.db_instance {
+            Ok(db.get()?.collection("threat_lists"))
+        } else {
+            Err(RepositoryError::UninitializedRepoError)
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use bson::Bson;
+use ipnet::Ipv4Net;
+use mongodb::coll::options::FindOptions;
+use crate::lookup::LocationSource;
+use crate::models::{Repository, RepositoryCollection, RepositoryError};
+use crate::models::ip::Ip;
+use crate::models::location::Location;
+
+#[derive(Debug, Clone)]
+pub enum LocationSelector {
+    GeonameId(String),
+    /// ISO 3166-1 alpha-2 code
+    Country(String),
+    City(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkPage {
+    /// Locations matching the selector
+    pub geoname_ids: Vec<String>,
+    pub total: i64,
+    pub networks: Vec<Ip>,
+}
+
+/// Networks of the `ip` collection mapped to the locations matching `selector`, ordered by address.
+/// `page` starts at 0.
+pub fn networks_for_location(repos: &RepositoryCollection, selector: &LocationSelector, page: i64, per_page: i64) -> Result<NetworkPage, RepositoryError> {
+    let location_filter = match selector {
+        LocationSelector::GeonameId(geoname_id) => doc! {"geoname_id": geoname_id.as_str()},
+        LocationSelector::Country(iso_code) => doc! {"country_iso_code": iso_code.to_uppercase()},
+        LocationSelector::City(city_name) => doc! {"city_name": city_name.as_str()},
+    };
+
+    let geoname_ids = repos.location.find(location_filter)?
+        .into_iter()
+        .map(|location| location.geoname_id)
+        .collect::<Vec<String>>();
+
+    if geoname_ids.is_empty() {
+        return Ok(NetworkPage {
+            geoname_ids,
+            total: 0,
+            networks: Vec::new(),
+        });
+    }
+
+    let ids = geoname_ids.iter().map(|id| Bson::String(id.clone())).collect::<Vec<Bson>>();
+
+    // Networks only known at the country level are mapped through their registered country, like lookups are
+    let network_filter = doc! {
+        "$or": [
+            { "geoname_id": { "$in": ids.clone() } },
+            { "geoname_id": "", "registered_country_geoname_id": { "$in": ids } }
+        ]
+    };
+
+    let total = repos.ip.count(Some(network_filter.clone()))?;
+
+    let mut options = FindOptions::new();
+    // A /24 can hold several networks, `_id` keeps the order stable from one page to the next
+    options.sort = Some(doc! {"net": 1, "sub": 1, "sub2": 1, "range_start": 1, "_id": 1});
+    options.skip = Some(page * per_page);
+    options.limit = Some(per_page);
+    let networks = repos.ip.find_with_options(network_filter, options)?;
+
+    Ok(NetworkPage {
+        geoname_ids,
+        total,
+        networks,
+    })
+}
+
+#[derive(Debug)]
+pub enum RangeError {
+    /// Some networks lack `range_start` and `range_end`, the block cannot be queried reliably
+    MissingRangeFields(i64),
+    Repository(RepositoryError),
+}
+
+impl From<RepositoryError> for RangeError {
+    fn from(e: RepositoryError) -> Self {
+        RangeError::Repository(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RangePage {
+    pub total: i64,
+    /// Networks of the page with their location, if any
+    pub networks: Vec<(Ip, Option<(Location, LocationSource)>)>,
+    /// Locations of every network overlapping the range, not only of the page
+    pub locations: Vec<Location>,
+}
+
+/// Networks of the `ip` collection overlapping `range`, ordered by address. `page` starts at 0.
+/// Relies on the `range_start` and `range_end` fields written by `spotme import`, collections imported
+/// before they existed are refused rather than answered with missing networks.
+pub fn networks_in_range(repos: &RepositoryCollection, range: &Ipv4Net, page: i64, per_page: i64) -> Result<RangePage, RangeError> {
+    let without_range = repos.ip.count(Some(doc! {"range_start": { "$exists": false }}))?;
+    if without_range > 0 {
+        return Err(RangeError::MissingRangeFields(without_range));
+    }
+
+    let network_filter = doc! {
+        "range_start": { "$lte": u32::from(range.broadcast()) as i64 },
+        "range_end": { "$gte": u32::from(range.network()) as i64 }
+    };
+
+    let total = repos.ip.count(Some(network_filter.clone()))?;
+
+    let mut options = FindOptions::new();
+    options.sort = Some(doc! {"range_start": 1, "_id": 1});
+    options.skip = Some(page * per_page);
+    options.limit = Some(per_page);
+    let networks = repos.ip.find_with_options(network_filter.clone(), options)?;
+
+    // Same fallback as lookups, so that country level networks are summarized by their registered country
+    let pipeline = vec![
+        doc! {"$match": network_filter},
+        doc! {"$group": {
+            "_id": Bson::Null,
+            "geoname_ids": { "$addToSet": { "$cond": [{ "$eq": ["$geoname_id", ""] }, "$registered_country_geoname_id", "$geoname_id"] } }
+        }},
+    ];
+
+    // Aggregated directly, `find_with_pipeline` would turn a failed aggregate into an empty summary
+    let summary = repos.ip.get_collection()?
+        .aggregate(pipeline, None)
+        .and_then(|mut cursor| cursor.next().transpose())
+        .map_err(RepositoryError::from)?;
+
+    let summary_ids = match summary.as_ref().and_then(|document| document.get_array("geoname_ids").ok()) {
+        Some(ids) => ids.iter().filter(|id| id.as_str().map_or(false, |id| !id.is_empty())).cloned().collect::<Vec<Bson>>(),
+        None => Vec::new(),
+    };
+
+    let mut page_ids = networks.iter()
+        .flat_map(|ip| vec![&ip.geoname_id, &ip.registered_country_geoname_id, &ip.represented_country_geoname_id])
+        .filter(|id| !id.is_empty())
+        .map(|id| Bson::String(id.clone()))
+        .collect::<Vec<Bson>>();
+    page_ids.extend(summary_ids.iter().cloned());
+
+    let locations = repos.location.find(doc! {"geoname_id": { "$in": page_ids }})?
+        .into_iter()
+        .map(|location| (location.geoname_id.clone(), location))
+        .collect::<HashMap<String, Location>>();
+
+    let networks = networks.into_iter()
+        .map(|ip| {
+            let location = [
+                (&ip.geoname_id, LocationSource::Geoname),
+                (&ip.registered_country_geoname_id, LocationSource::RegisteredCountry),
+                (&ip.represented_country_geoname_id, LocationSource::RepresentedCountry),
+            ].iter()
+                .filter_map(|(geoname_id, source)| locations.get(*geoname_id).map(|location| (location.clone(), *source)))
+                .next();
+
+            (ip, location)
+        })
+        .collect();
+
+    let summary_locations = summary_ids.iter()
+        .filter_map(|id| id.as_str().and_then(|id| locations.get(id)).cloned())
+        .collect();
+
+    Ok(RangePage {
+        total,
+        networks,
+        locations: summary_locations,
+    })
+}
@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use ipnet::IpNet;
+use crate::geo::valid_coordinates;
+use crate::models::{Repository, RepositoryCollection, RepositoryError};
+use crate::models::ip::Ip;
+use crate::models::location::Location;
+use crate::models::network_override::NetworkOverride;
+use crate::settings::Overrides;
+
+/// Network overrides of the settings file and of the `overrides` collection, matched in memory.
+#[derive(Clone)]
+pub struct OverrideTable {
+    repos: RepositoryCollection,
+    file_overrides: Arc<Vec<(IpNet, NetworkOverride)>>,
+    networks: Arc<RwLock<Vec<(IpNet, NetworkOverride)>>>,
+}
+
+impl OverrideTable {
+    pub fn load(repos: RepositoryCollection, config: &Overrides) -> Result<Self, String> {
+        let file_overrides = if config.file.is_empty() {
+            Vec::new()
+        } else {
+            read_file(&config.file)?
+        };
+
+        let table = OverrideTable {
+            repos,
+            file_overrides: Arc::new(file_overrides),
+            networks: Arc::new(RwLock::new(Vec::new())),
+        };
+
+        table.reload().map_err(|e| format!("Unable to load the network overrides: {:?}", e))?;
+
+        Ok(table)
+    }
+
+    /// Reads the `overrides` collection again, returns whether anything changed.
+    /// Invalid documents are skipped, they can only come from writes bypassing the admin API.
+    pub fn reload(&self) -> Result<bool, RepositoryError> {
+        let mut networks = self.file_overrides.as_ref().clone();
+
+        for network_override in self.repos.overrides.get_all()? {
+            match validate_override(&network_override) {
+                Ok(net) => networks.push((net, network_override)),
+                Err(e) => warn!("Ignoring the override of {}: {}", network_override.network, e),
+            }
+        }
+
+        let mut current = self.networks.write().expect("Overrides lock poisoned");
+
+        if *current == networks {
+            return Ok(false);
+        }
+
+        *current = networks;
+        Ok(true)
+    }
+
+    /// The most specific override containing `addr`.
+    pub fn find(&self, addr: &IpAddr) -> Option<(IpNet, NetworkOverride)> {
+        self.networks.read().expect("Overrides lock poisoned")
+            .iter()
+            .filter(|(net, _)| net.contains(addr))
+            .max_by_key(|(net, _)| net.prefix_len())
+            .cloned()
+    }
+}
+
+/// Checks an override before it is written, returning its network.
+pub fn validate_override(network_override: &NetworkOverride) -> Result<IpNet, String> {
+    let net = network_override.network.parse::<IpNet>().map_err(|_| format!("{:?} is not a valid CIDR", network_override.network))?;
+
+    if net != net.trunc() {
+        return Err(format!("{} has host bits set, use {}", net, net.trunc()));
+    }
+
+    match (network_override.latitude, network_override.longitude) {
+        (Some(latitude), Some(longitude)) if !valid_coordinates(latitude, longitude) => Err(format!("{},{} are not valid coordinates", latitude, longitude)),
+        (Some(_), None) | (None, Some(_)) => Err("latitude and longitude must be set together".to_string()),
+        _ if network_override.accuracy_radius.map_or(false, |radius| radius < 0) => Err("accuracy_radius cannot be negative".to_string()),
+        _ => Ok(net),
+    }
+}
+
+/// The network record an override stands for.
+pub fn override_network(net: &IpNet, network_override: &NetworkOverride) -> Ip {
+    let mut ip = Ip::new();
+    ip.id = network_override.id.clone();
+    ip.network = net.to_string();
+    ip.geoname_id = network_override.geoname_id.clone();
+    ip.latitude = network_override.latitude.map(|latitude| latitude.to_string()).unwrap_or_default();
+    ip.longitude = network_override.longitude.map(|longitude| longitude.to_string()).unwrap_or_default();
+    ip.accuracy_radius = network_override.accuracy_radius.map(|radius| radius.to_string()).unwrap_or_default();
+    ip
+}
+
+/// The location an override stands for.
+pub fn override_location(network_override: &NetworkOverride) -> Location {
+    let mut location = Location::new();
+    location.geoname_id = network_override.geoname_id.clone();
+    location.continent_name = network_override.continent_name.clone();
+    location.country_iso_code = network_override.country_iso_code.clone();
+    location.country_name = network_override.country_name.clone();
+    location.subdivision_1_name = network_override.subdivision_1_name.clone();
+    location.subdivision_2_name = network_override.subdivision_2_name.clone();
+    location.city_name = network_override.city_name.clone();
+    location.time_zone = network_override.time_zone.clone();
+    location
+}
+
+fn read_file(path: &str) -> Result<Vec<(IpNet, NetworkOverride)>, String> {
+    let file = File::open(path).map_err(|e| format!("Unable to open {}: {}", path, e))?;
+
+    // YAML being a superset of JSON, both formats are read the same way
+    let overrides: Vec<NetworkOverride> = serde_yaml::from_reader(file).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+
+    overrides.into_iter()
+        .map(|network_override| {
+            let net = validate_override(&network_override).map_err(|e| format!("{}: {}", path, e))?;
+            Ok((net, network_override))
+        })
+        .collect()
+}
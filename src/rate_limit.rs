@@ -0,0 +1,161 @@
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use ipnet::IpNet;
+use lru::LruCache;
+use crate::settings::RateLimit;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter, one bucket per client key.
+/// At most `max_clients` buckets are tracked, the least recently seen client is forgotten past that,
+/// which only ever gives it a full bucket back.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimit,
+    trusted_proxies: Arc<Vec<IpNet>>,
+    buckets: Arc<Mutex<LruCache<String, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimit) -> Result<Self, String> {
+        let trusted_proxies = config.trusted_proxies.iter()
+            .map(|proxy| match proxy.parse::<IpNet>() {
+                Ok(net) => Ok(net),
+                Err(_) => proxy.parse::<IpAddr>().map(IpNet::from).map_err(|_| format!("rate_limit.trusted_proxies: {:?} is neither an IP address nor a CIDR", proxy)),
+            })
+            .collect::<Result<Vec<IpNet>, String>>()?;
+
+        Ok(RateLimiter {
+            trusted_proxies: Arc::new(trusted_proxies),
+            buckets: Arc::new(Mutex::new(LruCache::new(config.max_clients.max(1)))),
+            config,
+        })
+    }
+
+    /// Whether `addr` is one of the configured reverse proxies.
+    pub fn is_trusted_proxy(&self, addr: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(addr))
+    }
+
+    /// Takes a token from the bucket of `key`, or returns the number of seconds to wait before retrying.
+    pub fn check(&self, key: &str) -> Result<(), u64> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), u64> {
+        if !self.config.enabled || self.config.requests_per_second <= 0.0 {
+            return Ok(());
+        }
+
+        let rate = self.config.requests_per_second;
+        let burst = self.config.burst.max(1.0);
+
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock poisoned");
+
+        let mut bucket = buckets.pop(&key.to_string()).unwrap_or(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+
+        bucket.tokens = (bucket.tokens + elapsed_secs(bucket.updated, now) * rate).min(burst);
+        bucket.updated = now;
+
+        let result = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / rate).ceil() as u64)
+        };
+
+        // Putting the bucket back marks the client as the most recently seen one
+        buckets.put(key.to_string(), bucket);
+
+        result
+    }
+}
+
+fn elapsed_secs(since: Instant, now: Instant) -> f64 {
+    let elapsed = now.duration_since(since);
+    elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limiter(requests_per_second: f64, burst: f64, max_clients: usize) -> RateLimiter {
+        RateLimiter::new(RateLimit {
+            enabled: true,
+            requests_per_second,
+            burst,
+            max_clients,
+            ..RateLimit::default()
+        }).expect("The settings are valid")
+    }
+
+    #[test]
+    fn allows_a_burst_then_asks_to_wait() {
+        let limiter = limiter(1.0, 3.0, 10);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at("ip:192.0.2.1", now), Ok(()));
+        }
+
+        assert_eq!(limiter.check_at("ip:192.0.2.1", now), Err(1));
+        // Other clients have their own bucket
+        assert_eq!(limiter.check_at("ip:192.0.2.2", now), Ok(()));
+    }
+
+    #[test]
+    fn refills_at_the_configured_rate_up_to_the_burst() {
+        let limiter = limiter(2.0, 2.0, 10);
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at("ip:192.0.2.1", now), Ok(()));
+        assert_eq!(limiter.check_at("ip:192.0.2.1", now), Ok(()));
+        assert_eq!(limiter.check_at("ip:192.0.2.1", now), Err(1));
+
+        // Half a second gives one token back at 2 requests per second
+        let later = now + Duration::from_millis(500);
+        assert_eq!(limiter.check_at("ip:192.0.2.1", later), Ok(()));
+        assert_eq!(limiter.check_at("ip:192.0.2.1", later), Err(1));
+
+        // A long pause never fills the bucket past the burst
+        let much_later = later + Duration::from_secs(60);
+        assert_eq!(limiter.check_at("ip:192.0.2.1", much_later), Ok(()));
+        assert_eq!(limiter.check_at("ip:192.0.2.1", much_later), Ok(()));
+        assert_eq!(limiter.check_at("ip:192.0.2.1", much_later), Err(1));
+    }
+
+    #[test]
+    fn forgets_the_least_recently_seen_client_past_the_cap() {
+        let limiter = limiter(1.0, 1.0, 2);
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at("ip:192.0.2.1", now), Ok(()));
+        assert_eq!(limiter.check_at("ip:192.0.2.2", now), Ok(()));
+        assert_eq!(limiter.check_at("ip:192.0.2.1", now), Err(1));
+
+        // 192.0.2.2 is now the least recently seen client and makes room for 192.0.2.3
+        assert_eq!(limiter.check_at("ip:192.0.2.3", now), Ok(()));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+
+        assert_eq!(limiter.check_at("ip:192.0.2.1", now), Err(1));
+        assert_eq!(limiter.check_at("ip:192.0.2.2", now), Ok(()));
+    }
+
+    #[test]
+    fn disabled_limiter_allows_everything() {
+        let limiter = RateLimiter::new(RateLimit::default()).expect("The settings are valid");
+
+        for _ in 0..100 {
+            assert_eq!(limiter.check("ip:192.0.2.1"), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::net::IpAddr;
+use ipnet::IpNet;
+
+/// An IANA special-purpose block, these never appear in the GeoLite2 data.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct ReservedRange {
+    pub network: &'static str,
+    pub purpose: &'static str,
+    /// Defining RFC
+    pub reference: &'static str,
+}
+
+const fn range(network: &'static str, purpose: &'static str, reference: &'static str) -> ReservedRange {
+    ReservedRange {
+        network,
+        purpose,
+        reference,
+    }
+}
+
+static RESERVED_RANGES: &[ReservedRange] = &[
+    range("0.0.0.0/8", "This network", "RFC 791"),
+    range("10.0.0.0/8", "Private-Use", "RFC 1918"),
+    range("100.64.0.0/10", "Shared Address Space (CGNAT)", "RFC 6598"),
+    range("127.0.0.0/8", "Loopback", "RFC 1122"),
+    range("169.254.0.0/16", "Link-Local", "RFC 3927"),
+    range("172.16.0.0/12", "Private-Use", "RFC 1918"),
+    range("192.0.0.0/24", "IETF Protocol Assignments", "RFC 6890"),
+    range("192.0.2.0/24", "Documentation (TEST-NET-1)", "RFC 5737"),
+    range("192.31.196.0/24", "AS112-v4", "RFC 7535"),
+    range("192.52.193.0/24", "AMT", "RFC 7450"),
+    range("192.88.99.0/24", "Deprecated (6to4 Relay Anycast)", "RFC 7526"),
+    range("192.168.0.0/16", "Private-Use", "RFC 1918"),
+    range("192.175.48.0/24", "Direct Delegation AS112 Service", "RFC 7534"),
+    range("198.18.0.0/15", "Benchmarking", "RFC 2544"),
+    range("198.51.100.0/24", "Documentation (TEST-NET-2)", "RFC 5737"),
+    range("203.0.113.0/24", "Documentation (TEST-NET-3)", "RFC 5737"),
+    range("224.0.0.0/4", "Multicast", "RFC 5771"),
+    range("240.0.0.0/4", "Reserved", "RFC 1112"),
+    range("255.255.255.255/32", "Limited Broadcast", "RFC 919"),
+    range("::/128", "Unspecified Address", "RFC 4291"),
+    range("::1/128", "Loopback Address", "RFC 4291"),
+    range("::ffff:0:0/96", "IPv4-mapped Address", "RFC 4291"),
+    range("64:ff9b::/96", "IPv4-IPv6 Translation", "RFC 6052"),
+    range("64:ff9b:1::/48", "IPv4-IPv6 Local-Use Translation", "RFC 8215"),
+    range("100::/64", "Discard-Only Address Block", "RFC 6666"),
+    range("2001::/23", "IETF Protocol Assignments", "RFC 2928"),
+    range("2001::/32", "TEREDO", "RFC 4380"),
+    range("2001:2::/48", "Benchmarking", "RFC 5180"),
+    range("2001:20::/28", "ORCHIDv2", "RFC 7343"),
+    range("2001:db8::/32", "Documentation", "RFC 3849"),
+    range("2002::/16", "6to4", "RFC 3056"),
+    range("fc00::/7", "Unique-Local", "RFC 4193"),
+    range("fe80::/10", "Link-Local Unicast", "RFC 4291"),
+    range("ff00::/8", "Multicast", "RFC 4291"),
+];
+
+lazy_static! {
+    /// Most specific blocks first, so that e.g. TEREDO wins over the IETF assignments containing it
+    static ref RESERVED_NETWORKS: Vec<(IpNet, &'static ReservedRange)> = {
+        let mut networks = RESERVED_RANGES.iter()
+            .map(|range| (range.network.parse::<IpNet>().expect("Will be ok"), range))
+            .collect::<Vec<_>>();
+
+        networks.sort_by(|a, b| b.0.prefix_len().cmp(&a.0.prefix_len()));
+        networks
+    };
+}
+
+/// The special-purpose block containing `addr`, if any.
+pub fn reserved_range(addr: &IpAddr) -> Option<&'static ReservedRange> {
+    RESERVED_NETWORKS.iter()
+        .find(|(network, _)| network.contains(addr))
+        .map(|(_, range)| *range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn purpose(addr: &str) -> Option<&'static str> {
+        reserved_range(&addr.parse().unwrap()).map(|range| range.purpose)
+    }
+
+    #[test]
+    fn classifies_private_and_shared_ipv4() {
+        assert_eq!(purpose("10.1.2.3"), Some("Private-Use"));
+        assert_eq!(purpose("172.31.255.255"), Some("Private-Use"));
+        assert_eq!(purpose("192.168.0.1"), Some("Private-Use"));
+        assert_eq!(purpose("100.64.0.1"), Some("Shared Address Space (CGNAT)"));
+        assert_eq!(purpose("100.127.255.255"), Some("Shared Address Space (CGNAT)"));
+    }
+
+    #[test]
+    fn classifies_loopback_documentation_and_multicast() {
+        assert_eq!(purpose("127.0.0.1"), Some("Loopback"));
+        assert_eq!(purpose("::1"), Some("Loopback Address"));
+        assert_eq!(purpose("192.0.2.10"), Some("Documentation (TEST-NET-1)"));
+        assert_eq!(purpose("198.51.100.10"), Some("Documentation (TEST-NET-2)"));
+        assert_eq!(purpose("203.0.113.10"), Some("Documentation (TEST-NET-3)"));
+        assert_eq!(purpose("2001:db8::1"), Some("Documentation"));
+        assert_eq!(purpose("224.0.0.251"), Some("Multicast"));
+        assert_eq!(purpose("ff02::fb"), Some("Multicast"));
+    }
+
+    #[test]
+    fn public_addresses_are_not_reserved() {
+        assert_eq!(purpose("8.8.8.8"), None);
+        assert_eq!(purpose("100.63.255.255"), None);
+        assert_eq!(purpose("172.32.0.1"), None);
+        assert_eq!(purpose("2606:4700:4700::1111"), None);
+    }
+
+    #[test]
+    fn most_specific_block_wins() {
+        assert_eq!(purpose("2001::1"), Some("TEREDO"));
+        assert_eq!(purpose("2001:2::1"), Some("Benchmarking"));
+        assert_eq!(purpose("2001:100::1"), Some("IETF Protocol Assignments"));
+        assert_eq!(purpose("64:ff9b:1::1"), Some("IPv4-IPv6 Local-Use Translation"));
+        assert_eq!(purpose("255.255.255.255"), Some("Limited Broadcast"));
+        assert_eq!(purpose("255.255.255.254"), Some("Reserved"));
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use crate::cache::{CacheStats, TtlCache};
+use crate::settings;
+
+#[derive(Debug)]
+pub enum ResolveError {
+    InvalidHost,
+    /// The host has no address, or the resolver could not be reached
+    NotFound(String),
+}
+
+/// Turns hostnames into the addresses to geolocate.
+pub trait Resolver: Send + Sync {
+    /// Every A and AAAA address of `host`, without duplicates.
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, ResolveError>;
+
+    /// The PTR name of `addr`, `None` when it has none.
+    fn reverse(&self, addr: &IpAddr) -> Result<Option<String>, ResolveError>;
+}
+
+/// The static hosts file when the settings name one, the system resolver otherwise.
+pub fn from_settings(config: &settings::Resolver) -> Result<Arc<dyn Resolver>, String> {
+    if config.hosts_file.is_empty() {
+        Ok(Arc::new(SystemResolver::new(config)))
+    } else {
+        Ok(Arc::new(HostsFileResolver::load(&config.hosts_file)?))
+    }
+}
+
+/// Resolves through the operating system, i.e. `/etc/hosts` then DNS.
+/// `getaddrinfo` has no timeout of its own, so queries go through `with_timeout` with `resolve_timeout_ms`,
+/// at most `resolve_max_pending` at once.
+pub struct SystemResolver {
+    timeout: Duration,
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: usize,
+}
+
+impl SystemResolver {
+    pub fn new(config: &settings::Resolver) -> Self {
+        SystemResolver {
+            timeout: Duration::from_millis(config.resolve_timeout_ms),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max_in_flight: config.resolve_max_pending,
+        }
+    }
+}
+
+impl Resolver for SystemResolver {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, ResolveError> {
+        if !valid_host(host) {
+            return Err(ResolveError::InvalidHost);
+        }
+
+        if self.in_flight.fetch_add(1, Ordering::SeqCst) >= self.max_in_flight {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return Err(ResolveError::NotFound(format!("{} was not resolved, too many queries are in flight", host)));
+        }
+
+        let in_flight = self.in_flight.clone();
+        let query = host.to_string();
+
+        let result = with_timeout(self.timeout, move || {
+            // The port is required by `ToSocketAddrs` but irrelevant here
+            let result = (query.as_str(), 0).to_socket_addrs().map(|socket_addrs| socket_addrs.map(|socket_addr| socket_addr.ip()).collect::<Vec<IpAddr>>());
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            result
+        });
+
+        let resolved = match result {
+            Some(result) => result.map_err(|e| ResolveError::NotFound(e.to_string()))?,
+            None => return Err(ResolveError::NotFound(format!("{} was not resolved within {} ms", host, self.timeout.as_millis()))),
+        };
+
+        let mut addrs = Vec::new();
+        for addr in resolved {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+
+        if addrs.is_empty() {
+            return Err(ResolveError::NotFound(format!("{} has no address", host)));
+        }
+
+        Ok(addrs)
+    }
+
+    fn reverse(&self, addr: &IpAddr) -> Result<Option<String>, ResolveError> {
+        match dns_lookup::lookup_addr(addr) {
+            // getnameinfo falls back on the numeric form when there is no PTR record
+            Ok(name) => Ok(if name.parse::<IpAddr>().is_ok() { None } else { Some(name) }),
+            Err(e) => Err(ResolveError::NotFound(e.to_string())),
+        }
+    }
+}
+
+/// Resolves from a static file in the `/etc/hosts` format only, so that lookups are reproducible.
+pub struct HostsFileResolver {
+    hosts: HashMap<String, Vec<IpAddr>>,
+    /// First name given to each address, as the system resolver answers PTR queries from `/etc/hosts`
+    names: HashMap<IpAddr, String>,
+}
+
+impl HostsFileResolver {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Each line holds an address followed by its names, `#` starts a comment.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut hosts: HashMap<String, Vec<IpAddr>> = HashMap::new();
+        let mut names = HashMap::new();
+
+        for (number, line) in content.lines().enumerate() {
+            let mut fields = line.split('#').next().unwrap_or_default().split_whitespace();
+
+            let addr = match fields.next() {
+                Some(addr) => addr.parse::<IpAddr>().map_err(|_| format!("line {}: {:?} is not an IP address", number + 1, addr))?,
+                None => continue,
+            };
+
+            for name in fields {
+                names.entry(addr).or_insert_with(|| name.to_lowercase());
+
+                let addrs = hosts.entry(name.to_lowercase()).or_default();
+                if !addrs.contains(&addr) {
+                    addrs.push(addr);
+                }
+            }
+        }
+
+        Ok(HostsFileResolver {
+            hosts,
+            names,
+        })
+    }
+}
+
+impl Resolver for HostsFileResolver {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, ResolveError> {
+        if !valid_host(host) {
+            return Err(ResolveError::InvalidHost);
+        }
+
+        self.hosts.get(&host.trim_end_matches('.').to_lowercase())
+            .cloned()
+            .ok_or_else(|| ResolveError::NotFound(format!("{} is not in the hosts file", host)))
+    }
+
+    fn reverse(&self, addr: &IpAddr) -> Result<Option<String>, ResolveError> {
+        Ok(self.names.get(addr).cloned())
+    }
+}
+
+/// PTR lookups bounded by a timeout through `with_timeout`, so that a slow DNS server never holds a geolocation
+/// answer back. A query outliving its timeout caches its answer for the next lookups.
+#[derive(Clone)]
+pub struct ReverseDns {
+    resolver: Arc<dyn Resolver>,
+    timeout: Duration,
+    cache: TtlCache<IpAddr, Option<String>>,
+    /// Addresses with a query in flight, never queried twice at once
+    pending: Arc<Mutex<HashSet<IpAddr>>>,
+    /// Each query in flight holds a thread, lookups go without a hostname past this many
+    max_pending: usize,
+}
+
+impl ReverseDns {
+    pub fn new(resolver: Arc<dyn Resolver>, config: &settings::Resolver) -> Self {
+        ReverseDns {
+            resolver,
+            timeout: Duration::from_millis(config.rdns_timeout_ms),
+            cache: TtlCache::new(config.rdns_cache_entries, Duration::from_secs(config.rdns_cache_ttl_secs)),
+            pending: Arc::new(Mutex::new(HashSet::new())),
+            max_pending: config.rdns_max_pending,
+        }
+    }
+
+    /// The PTR name of `addr`, `None` when it has none, when the resolver did not answer in time
+    /// or when too many queries are already in flight.
+    pub fn hostname(&self, addr: IpAddr) -> Option<String> {
+        if let Some(hostname) = self.cache.get(&addr) {
+            return hostname;
+        }
+
+        {
+            let mut pending = self.pending.lock().expect("Reverse DNS lock poisoned");
+
+            if pending.len() >= self.max_pending {
+                debug!("Skipping the reverse lookup of {}, {} queries are in flight", addr, pending.len());
+                return None;
+            }
+
+            if !pending.insert(addr) {
+                return None;
+            }
+        }
+
+        let reverse_dns = self.clone();
+
+        let hostname = with_timeout(self.timeout, move || {
+            let hostname = match reverse_dns.resolver.reverse(&addr) {
+                Ok(hostname) => hostname,
+                Err(e) => {
+                    debug!("Reverse lookup of {} failed: {:?}", addr, e);
+                    None
+                }
+            };
+
+            reverse_dns.cache.insert(addr, hostname.clone());
+            reverse_dns.pending.lock().expect("Reverse DNS lock poisoned").remove(&addr);
+            hostname
+        });
+
+        hostname.unwrap_or_else(|| {
+            debug!("Reverse lookup of {} timed out", addr);
+            None
+        })
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+}
+
+/// Runs `query` on its own thread and waits at most `timeout` for its answer, `None` past it.
+/// The thread cannot be interrupted: a query outliving its timeout runs to completion in the background
+/// and its answer is dropped, which is why callers cap the number of queries in flight.
+fn with_timeout<T, F>(timeout: Duration, query: F) -> Option<T> where T: Send + 'static, F: FnOnce() -> T + Send + 'static {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _ = sender.send(query());
+    });
+
+    receiver.recv_timeout(timeout).ok()
+}
+
+/// Letters, digits, `-` and `_` labels separated by dots, at most 253 characters.
+pub fn valid_host(host: &str) -> bool {
+    let host = host.trim_end_matches('.');
+
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTS: &str = "\
+# Static hosts of the test datasets
+203.0.113.10    www.example.com example.com
+203.0.113.11    www.example.com   # second address of the same name
+2001:db8::10    Mail.Example.com
+
+198.51.100.1    gateway
+";
+
+    fn resolver() -> HostsFileResolver {
+        HostsFileResolver::parse(HOSTS).expect("The hosts are valid")
+    }
+
+    fn addr(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn resolves_every_address_of_a_name_in_file_order() {
+        assert_eq!(resolver().resolve("www.example.com").unwrap(), vec![addr("203.0.113.10"), addr("203.0.113.11")]);
+        assert_eq!(resolver().resolve("example.com").unwrap(), vec![addr("203.0.113.10")]);
+        assert_eq!(resolver().resolve("mail.example.com").unwrap(), vec![addr("2001:db8::10")]);
+    }
+
+    #[test]
+    fn resolves_regardless_of_case_and_trailing_dot() {
+        assert_eq!(resolver().resolve("WWW.Example.COM.").unwrap(), vec![addr("203.0.113.10"), addr("203.0.113.11")]);
+        assert_eq!(resolver().resolve("gateway.").unwrap(), vec![addr("198.51.100.1")]);
+    }
+
+    #[test]
+    fn rejects_unknown_and_invalid_hosts() {
+        match resolver().resolve("unknown.example.com") {
+            Err(ResolveError::NotFound(_)) => {}
+            other => panic!("Expected NotFound, got {:?}", other),
+        }
+
+        for host in &["", ".", "www..example.com", "www.example.com/path", "203.0.113.10:80"] {
+            match resolver().resolve(host) {
+                Err(ResolveError::InvalidHost) => {}
+                other => panic!("Expected InvalidHost for {:?}, got {:?}", host, other),
+            }
+        }
+    }
+
+    #[test]
+    fn reverses_to_the_first_name_of_an_address() {
+        assert_eq!(resolver().reverse(&addr("203.0.113.10")).unwrap(), Some("www.example.com".to_string()));
+        assert_eq!(resolver().reverse(&addr("2001:db8::10")).unwrap(), Some("mail.example.com".to_string()));
+        assert_eq!(resolver().reverse(&addr("192.0.2.1")).unwrap(), None);
+    }
+
+    #[test]
+    fn reports_the_line_of_an_invalid_address() {
+        match HostsFileResolver::parse("203.0.113.10 www.example.com\nnot-an-address www.example.org\n") {
+            Err(e) => assert!(e.starts_with("line 2:"), "Unexpected error {:?}", e),
+            Ok(_) => panic!("The second line is not valid"),
+        }
+    }
+
+    #[test]
+    fn reverse_dns_answers_from_the_hosts_file() {
+        let reverse_dns = ReverseDns::new(Arc::new(resolver()), &settings::Resolver::default());
+
+        assert_eq!(reverse_dns.hostname(addr("198.51.100.1")), Some("gateway".to_string()));
+        assert_eq!(reverse_dns.hostname(addr("192.0.2.1")), None);
+    }
+}
@@ -4,7 +4,7 @@ use std;
 use std::io::Write;
 use std::fs::File;
 use log::LevelFilter;
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, SubCommand};
 use config::{ConfigError, Config, File as ConfigFile, Environment};
 
 const CONFIGURATION_FILE_NAME: &'static str = "spotme_conf";
@@ -44,11 +44,211 @@ pub struct Server {
     pub mongo_uri: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
+pub struct ApiKeyConfig {
+    pub key: String,
+    pub name: String,
+    /// Maximum number of requests per UTC day, 0 means unlimited
+    pub daily_quota: i64,
+    /// Grants access to the `/admin` endpoints
+    pub admin: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Auth {
+    pub enabled: bool,
+    pub header: String,
+    /// Keys accepted in addition to the ones stored in the `api_keys` collection
+    pub keys: Vec<ApiKeyConfig>,
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Auth {
+            enabled: false,
+            header: "X-Api-Key".to_string(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct RateLimit {
+    pub enabled: bool,
+    /// Tokens added to each client's bucket every second
+    pub requests_per_second: f64,
+    /// Size of each client's bucket, i.e. how many requests can be made in a burst
+    pub burst: f64,
+    /// Addresses or CIDRs of the reverse proxies whose `X-Forwarded-For` and `X-Real-IP` headers are honoured
+    pub trusted_proxies: Vec<String>,
+    /// Maximum number of clients whose bucket is tracked, the least recently seen one is forgotten past it
+    pub max_clients: usize,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            enabled: false,
+            requests_per_second: 10.0,
+            burst: 20.0,
+            trusted_proxies: Vec::new(),
+            max_clients: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Cache {
+    /// Maximum number of lookup results kept in memory, 0 disables the cache
+    pub max_entries: usize,
+    pub ttl_secs: u64,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache {
+            max_entries: 10_000,
+            ttl_secs: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Dataset {
+    /// How often the server checks whether another dataset version was activated
+    pub poll_interval_secs: u64,
+}
+
+impl Default for Dataset {
+    fn default() -> Self {
+        Dataset {
+            poll_interval_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
+pub struct Overrides {
+    /// YAML or JSON list of network overrides, applied in addition to the `overrides` collection
+    pub file: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Resolver {
+    /// Hosts file in the `/etc/hosts` format answering `host` and `rdns` lookups instead of the system resolver
+    pub hosts_file: String,
+    /// How long a `host` lookup waits for the system resolver before failing
+    pub resolve_timeout_ms: u64,
+    /// Maximum number of `host` resolutions in flight through the system resolver, each one holding a thread
+    pub resolve_max_pending: usize,
+    /// How long a lookup waits for its PTR record before answering without it
+    pub rdns_timeout_ms: u64,
+    /// Maximum number of PTR answers kept in memory, 0 disables the cache
+    pub rdns_cache_entries: usize,
+    pub rdns_cache_ttl_secs: u64,
+    /// Maximum number of PTR queries in flight, each one holding a thread until the resolver answers
+    pub rdns_max_pending: usize,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver {
+            hosts_file: String::new(),
+            resolve_timeout_ms: 2000,
+            resolve_max_pending: 32,
+            rdns_timeout_ms: 500,
+            rdns_cache_entries: 10_000,
+            rdns_cache_ttl_secs: 3600,
+            rdns_max_pending: 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DatasetCommand {
+    List,
+    Activate(String),
+    Rollback,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportCommand {
+    pub version: String,
+    pub blocks: String,
+    pub locations: String,
+    pub source: String,
+    pub build_date: String,
+    pub activate: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EnrichFormat {
+    Csv,
+    JsonLines,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnrichCommand {
+    /// Path of the input, `-` for stdin
+    pub input: String,
+    /// Path of the output, `-` for stdout
+    pub output: String,
+    /// CSV column holding the IPs, the input is read as one IP per line when `None`
+    pub column: Option<String>,
+    pub format: EnrichFormat,
+    pub threads: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum ThreatsCommand {
+    List,
+    /// Replaces the ranges of `list` with the ones of `file`
+    Import {
+        list: String,
+        /// `tor`, `anonymous_proxy` or `hosting`
+        kind: String,
+        file: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    Serve,
+    Dataset(DatasetCommand),
+    Import(ImportCommand),
+    Enrich(EnrichCommand),
+    /// Looks a single IP up, printing diagnostics
+    Lookup(String),
+    Threats(ThreatsCommand),
+    Verify,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::Serve
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(default)]
 pub struct Settings {
     pub loglevel: String,
     pub server: Server,
+    pub auth: Auth,
+    pub rate_limit: RateLimit,
+    pub cache: Cache,
+    pub dataset: Dataset,
+    pub overrides: Overrides,
+    pub resolver: Resolver,
+    #[serde(skip)]
+    pub command: Command,
 }
 
 impl Default for Settings {
@@ -57,7 +257,14 @@ impl Default for Settings {
             loglevel: "info".to_string(),
             server: Server {
                 mongo_uri: String::new()
-            }
+            },
+            auth: Auth::default(),
+            rate_limit: RateLimit::default(),
+            cache: Cache::default(),
+            dataset: Dataset::default(),
+            overrides: Overrides::default(),
+            resolver: Resolver::default(),
+            command: Command::Serve,
         }
     }
 }
@@ -105,6 +312,47 @@ impl Settings {
             settings.server.mongo_uri = uri.to_string();
         }
 
+        settings.command = match matches.subcommand() {
+            ("dataset", Some(dataset)) => Command::Dataset(match dataset.subcommand() {
+                ("activate", Some(activate)) => DatasetCommand::Activate(activate.value_of("VERSION").unwrap_or_default().to_string()),
+                ("rollback", _) => DatasetCommand::Rollback,
+                _ => DatasetCommand::List,
+            }),
+            ("import", Some(import)) => {
+                let version = import.value_of("VERSION").unwrap_or_default().to_string();
+
+                Command::Import(ImportCommand {
+                    blocks: import.value_of("blocks").unwrap_or_default().to_string(),
+                    locations: import.value_of("locations").unwrap_or_default().to_string(),
+                    source: import.value_of("source").unwrap_or_default().to_string(),
+                    build_date: import.value_of("build-date").map(|date| date.to_string()).unwrap_or_else(|| version.clone()),
+                    activate: import.is_present("activate"),
+                    version,
+                })
+            }
+            ("enrich", Some(enrich)) => Command::Enrich(EnrichCommand {
+                input: enrich.value_of("INPUT").unwrap_or("-").to_string(),
+                output: enrich.value_of("output").unwrap_or("-").to_string(),
+                column: enrich.value_of("column").map(|column| column.to_string()),
+                format: match enrich.value_of("format") {
+                    Some("jsonl") => EnrichFormat::JsonLines,
+                    _ => EnrichFormat::Csv,
+                },
+                threads: enrich.value_of("threads").unwrap_or("4").parse::<usize>()?,
+            }),
+            ("lookup", Some(lookup)) => Command::Lookup(lookup.value_of("IP").unwrap_or_default().to_string()),
+            ("threats", Some(threats)) => Command::Threats(match threats.subcommand() {
+                ("import", Some(import)) => ThreatsCommand::Import {
+                    list: import.value_of("LIST").unwrap_or_default().to_string(),
+                    kind: import.value_of("kind").unwrap_or_default().to_string(),
+                    file: import.value_of("file").unwrap_or_default().to_string(),
+                },
+                _ => ThreatsCommand::List,
+            }),
+            ("verify", _) => Command::Verify,
+            _ => Command::Serve,
+        };
+
         if let Some(config_path) = matches.value_of("save-config") {
             let mut file_path = Path::new(config_path).to_owned();
 
@@ -209,4 +457,141 @@ fn create_command_line_app<'a, 'b>() -> App<'a, 'b> {
             .help("Show the current config before startup")
             .takes_value(false)
         )
+        .subcommand(SubCommand::with_name("dataset")
+            .about("Manage the versioned geolocation datasets")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("list")
+                .about("List the available dataset versions")
+            )
+            .subcommand(SubCommand::with_name("activate")
+                .about("Serve the given dataset version")
+                .arg(Arg::with_name("VERSION")
+                    .help("Version suffix of the ip_<VERSION> and location_<VERSION> collections")
+                    .required(true)
+                    .index(1)
+                )
+            )
+            .subcommand(SubCommand::with_name("rollback")
+                .about("Serve the previously active dataset version again")
+            )
+        )
+        .subcommand(SubCommand::with_name("import")
+            .about("Import GeoLite2 City CSV files as a new dataset version")
+            .arg(Arg::with_name("VERSION")
+                .help("Version suffix of the collections to create, e.g. 2026_10_13")
+                .required(true)
+                .index(1)
+            )
+            .arg(Arg::with_name("blocks")
+                .long("blocks")
+                .value_name("PATH")
+                .help("Path of the GeoLite2-City-Blocks-IPv4.csv file")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("locations")
+                .long("locations")
+                .value_name("PATH")
+                .help("Path of the GeoLite2-City-Locations-<locale>.csv file")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("source")
+                .long("source")
+                .value_name("SOURCE")
+                .help("Name of the data source recorded in the dataset metadata")
+                .takes_value(true)
+                .default_value("GeoLite2-City")
+            )
+            .arg(Arg::with_name("build-date")
+                .long("build-date")
+                .value_name("DATE")
+                .help("Build date of the data source (defaults to VERSION)")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("activate")
+                .long("activate")
+                .help("Serve the new dataset once it is imported")
+                .takes_value(false)
+            )
+        )
+        .subcommand(SubCommand::with_name("enrich")
+            .about("Geolocate the IPs of a file, appending geo_* columns to each row")
+            .arg(Arg::with_name("INPUT")
+                .help("File to read, one IP per line unless --column is given, `-` for stdin")
+                .default_value("-")
+                .index(1)
+            )
+            .arg(Arg::with_name("column")
+                .long("column")
+                .value_name("NAME")
+                .help("Read the input as CSV with a header and look up the IPs of this column")
+                .takes_value(true)
+                .empty_values(false)
+            )
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("PATH")
+                .help("File to write, `-` for stdout")
+                .takes_value(true)
+                .default_value("-")
+            )
+            .arg(Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("CSV, or one JSON object per line")
+                .takes_value(true)
+                .possible_values(&["csv", "jsonl"])
+                .default_value("csv")
+            )
+            .arg(Arg::with_name("threads")
+                .long("threads")
+                .value_name("COUNT")
+                .help("Number of lookup threads")
+                .takes_value(true)
+                .default_value("4")
+            )
+        )
+        .subcommand(SubCommand::with_name("lookup")
+            .about("Look an IP up and explain the answer: candidate networks, selected network and location fallback")
+            .arg(Arg::with_name("IP")
+                .help("Address to look up")
+                .required(true)
+                .index(1)
+            )
+        )
+        .subcommand(SubCommand::with_name("threats")
+            .about("Manage the Tor exit, anonymous proxy and hosting lists flagged by lookups")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("list")
+                .about("List the imported threat lists")
+            )
+            .subcommand(SubCommand::with_name("import")
+                .about("Import a file of IPv4 addresses, CIDRs or start-end ranges, replacing the list of the same name")
+                .arg(Arg::with_name("LIST")
+                    .help("Name of the list, e.g. tor")
+                    .required(true)
+                    .index(1)
+                )
+                .arg(Arg::with_name("kind")
+                    .long("kind")
+                    .value_name("KIND")
+                    .help("Flag set on the addresses of the list")
+                    .takes_value(true)
+                    .possible_values(&["tor", "anonymous_proxy", "hosting"])
+                    .required(true)
+                )
+                .arg(Arg::with_name("file")
+                    .long("file")
+                    .value_name("PATH")
+                    .help("Path of the list, one entry per line, `#` starts a comment")
+                    .takes_value(true)
+                    .required(true)
+                )
+            )
+        )
+        .subcommand(SubCommand::with_name("verify")
+            .about("Check the integrity of the active dataset, exits with a non-zero code on problems")
+        )
 }
\ No newline at end of file
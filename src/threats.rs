@@ -0,0 +1,42 @@
+use std::net::IpAddr;
+use mongodb::coll::options::FindOptions;
+use crate::models::{Repository, RepositoryCollection, RepositoryError};
+use crate::models::threat::ThreatKind;
+
+/// Whether an address appears in the imported threat lists, by kind of list.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ThreatFlags {
+    pub is_tor: bool,
+    pub is_anonymous_proxy: bool,
+    pub is_hosting: bool,
+}
+
+/// Threat lists only hold IPv4 ranges, IPv6 addresses are never flagged.
+/// The ranges of a list never overlap, so the one starting last before `addr` is the only one that may contain it.
+pub fn threat_flags(repos: &RepositoryCollection, addr: &IpAddr) -> Result<ThreatFlags, RepositoryError> {
+    let mut flags = ThreatFlags::default();
+
+    let address = match addr {
+        IpAddr::V4(addr_v4) => u32::from(*addr_v4) as i64,
+        IpAddr::V6(_) => return Ok(flags),
+    };
+
+    for active in repos.threat_lists()? {
+        let mut options = FindOptions::new();
+        options.sort = Some(doc! {"start": -1});
+        options.limit = Some(1);
+
+        let filter = doc! {"list": active.list.as_str(), "generation": active.generation.as_str(), "start": { "$lte": address }};
+        let range = repos.threats.find_with_options(filter, options)?.into_iter().next();
+
+        if let Some(range) = range.filter(|range| range.end >= address) {
+            match range.kind {
+                ThreatKind::Tor => flags.is_tor = true,
+                ThreatKind::AnonymousProxy => flags.is_anonymous_proxy = true,
+                ThreatKind::Hosting => flags.is_hosting = true,
+            }
+        }
+    }
+
+    Ok(flags)
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Offset, Utc};
+use chrono_tz::{OffsetComponents, Tz};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TimeZoneInfo {
+    pub name: String,
+    /// e.g. `-04:00`
+    pub utc_offset: String,
+    pub utc_offset_seconds: i32,
+    /// Whether daylight saving time is in effect
+    pub dst: bool,
+    /// RFC 3339 local date and time
+    pub local_time: String,
+}
+
+/// Current offset of an IANA time zone, computed from the tz database embedded at build time.
+/// Returns `None` for unknown zone names.
+pub fn time_zone_info(name: &str, now: DateTime<Utc>) -> Option<TimeZoneInfo> {
+    let tz = name.parse::<Tz>().ok()?;
+    let local = now.with_timezone(&tz);
+    let utc_offset_seconds = local.offset().fix().local_minus_utc();
+
+    Some(TimeZoneInfo {
+        name: tz.name().to_string(),
+        utc_offset: format_offset(utc_offset_seconds),
+        utc_offset_seconds,
+        dst: local.offset().dst_offset().num_seconds() != 0,
+        local_time: local.to_rfc3339(),
+    })
+}
+
+fn format_offset(offset_seconds: i32) -> String {
+    let sign = if offset_seconds < 0 { '-' } else { '+' };
+    let minutes = offset_seconds.abs() / 60;
+
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}